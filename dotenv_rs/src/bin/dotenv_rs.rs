@@ -1,7 +1,9 @@
 extern crate dotenv_rs;
 
-use clap::{App, AppSettings, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use dotenv_rs::{Diagnostic, DotenvLoader, ExportFormat, Severity};
 use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
 use std::process::{exit, Command};
 
 macro_rules! die {
@@ -22,14 +24,138 @@ fn make_command(name: &str, args: Vec<&str>) -> Command {
         command.arg(arg);
     }
 
-    return command;
+    command
+}
+
+/// Runs the `check` subcommand, exiting with 1 if any error (or, if denied, warning) is found.
+fn check(matches: &ArgMatches, loader: &DotenvLoader) -> ! {
+    let paths: Vec<PathBuf> = match matches.values_of("FILES") {
+        Some(files) => files.map(PathBuf::from).collect(),
+        None => loader
+            .find()
+            .unwrap_or_else(|e| die!("error: failed to find .env file: {}", e)),
+    };
+    let diagnostics = dotenv_rs::lint_files(&paths)
+        .unwrap_or_else(|e| die!("error: failed to read .env file: {}", e));
+
+    if matches.value_of("FORMAT") == Some("json") {
+        let items: Vec<String> = diagnostics.iter().map(Diagnostic::to_json).collect();
+        println!("[{}]", items.join(","));
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{}", diagnostic);
+        }
+    }
+
+    let threshold = if matches.is_present("DENY_WARNINGS") {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+    let failed = diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity() >= threshold);
+    exit(if failed { 1 } else { 0 });
+}
+
+/// Runs the `diff-example` subcommand, exiting with 1 if a required key is missing or empty.
+fn diff_example(matches: &ArgMatches, loader: &DotenvLoader) -> ! {
+    let env_path = match matches.value_of("ENV") {
+        Some(path) => PathBuf::from(path),
+        None => loader
+            .find()
+            .map(|mut paths| paths.remove(0))
+            .unwrap_or_else(|e| die!("error: failed to find .env file: {}", e)),
+    };
+    let example_path = match matches.value_of("EXAMPLE") {
+        Some(path) => PathBuf::from(path),
+        None => env_path.with_file_name(".env.example"),
+    };
+    let report = dotenv_rs::check_against_example(&env_path, &example_path)
+        .unwrap_or_else(|e| die!("error: failed to compare with the example: {}", e));
+
+    for key in &report.missing {
+        println!("missing: {}", key);
+    }
+    for key in &report.empty {
+        println!("empty: {}", key);
+    }
+    for key in &report.extra {
+        println!("extra: {}", key);
+    }
+    exit(if report.is_ok() { 0 } else { 1 });
+}
+
+fn location(path: &Option<PathBuf>, line: usize) -> String {
+    match path {
+        Some(path) => format!("{}:{}", path.display(), line),
+        None => format!("line {}", line),
+    }
+}
+
+/// Runs the `explain` subcommand, exiting with 1 if the variable is unset.
+fn explain(matches: &ArgMatches, loader: &DotenvLoader) -> ! {
+    let key = matches.value_of("KEY").unwrap();
+    let explanation = loader
+        .explain(key)
+        .unwrap_or_else(|e| die!("error: failed to load environment: {}", e));
+
+    match &explanation.value {
+        Some(value) => println!("{}={}", key, value),
+        None => println!("{} is not set", key),
+    }
+    println!("source: {}", explanation.source);
+    if let Some(definition) = &explanation.definition {
+        println!("defined at {}", location(&definition.path, definition.line));
+    }
+    if explanation.shadowed {
+        println!("shadowed by the environment");
+    }
+    for step in &explanation.references {
+        let indent = "  ".repeat(step.depth);
+        match (&step.value, &step.definition) {
+            (Some(value), Some(definition)) => println!(
+                "{}${} = {} ({}, {})",
+                indent,
+                step.name,
+                value,
+                step.source,
+                location(&definition.path, definition.line)
+            ),
+            (Some(value), None) => {
+                println!("{}${} = {} ({})", indent, step.name, value, step.source)
+            }
+            (None, _) => println!("{}${} is not set", indent, step.name),
+        }
+    }
+    exit(if explanation.value.is_some() { 0 } else { 1 });
+}
+
+/// Runs the `export` subcommand, printing the variables of the files in the given format.
+fn export(matches: &ArgMatches, loader: &DotenvLoader) -> ! {
+    let format = matches
+        .value_of("FORMAT")
+        .and_then(ExportFormat::from_name)
+        .unwrap();
+    let mut vars: Vec<(String, String)> = loader
+        .to_map()
+        .unwrap_or_else(|e| die!("error: failed to load environment: {}", e))
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or_default()))
+        .collect();
+    vars.sort();
+    let output = dotenv_rs::export_string(vars, format)
+        .unwrap_or_else(|e| die!("error: failed to export as {}: {}", format, e));
+    print!("{}", output);
+    exit(0);
 }
 
 fn main() {
     let matches = App::new("dotenv")
         .about("Run a command using the environment in a .env file")
-        .usage("dotenv <COMMAND> [ARGS]...")
+        .usage("dotenv <COMMAND> [ARGS]...\n    dotenv tool <SUBCOMMAND>")
         .setting(AppSettings::AllowExternalSubcommands)
+        .setting(AppSettings::DisableHelpSubcommand)
         .setting(AppSettings::ArgRequiredElseHelp)
         .setting(AppSettings::UnifiedHelpMessage)
         .arg(
@@ -39,13 +165,115 @@ fn main() {
                 .takes_value(true)
                 .help("Use a specific .env file (defaults to .env)"),
         )
+        .arg(
+            Arg::with_name("MODE")
+                .short("m")
+                .long("mode")
+                .takes_value(true)
+                .conflicts_with("FILE")
+                .help("Load .env, .env.local, .env.<MODE> and .env.<MODE>.local"),
+        )
+        .arg(
+            Arg::with_name("VERBOSE")
+                .short("v")
+                .long("verbose")
+                .help("Print what happened to each variable, without the values"),
+        )
+        .arg(
+            Arg::with_name("OVERRIDE")
+                .short("o")
+                .long("override")
+                .help("Let values from the .env file replace existing environment variables"),
+        )
+        .subcommand(
+            SubCommand::with_name("tool")
+                .about("Work on the .env files instead of running a command")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about("Check .env files for errors and common mistakes")
+                        .arg(
+                            Arg::with_name("FILES")
+                                .multiple(true)
+                                .help("The files to check (defaults to the files loaded)"),
+                        )
+                        .arg(
+                            Arg::with_name("FORMAT")
+                                .long("format")
+                                .takes_value(true)
+                                .possible_values(&["text", "json"])
+                                .default_value("text")
+                                .help("The output format"),
+                        )
+                        .arg(
+                            Arg::with_name("DENY_WARNINGS")
+                                .long("deny-warnings")
+                                .help("Exit with an error status on warnings too"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("diff-example")
+                        .about("Compare a .env file with the keys listed in its example file")
+                        .arg(
+                            Arg::with_name("ENV")
+                                .help("The .env file (defaults to the first file loaded)"),
+                        )
+                        .arg(Arg::with_name("EXAMPLE").help(
+                            "The example file (defaults to .env.example next to the .env file)",
+                        )),
+                )
+                .subcommand(
+                    SubCommand::with_name("explain")
+                        .about(
+                            "Show where a variable gets its value from, without running anything",
+                        )
+                        .arg(
+                            Arg::with_name("KEY")
+                                .required(true)
+                                .help("The variable to explain"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("export")
+                        .about("Print the variables of the .env file for a shell or another tool")
+                        .arg(
+                            Arg::with_name("FORMAT")
+                                .long("format")
+                                .takes_value(true)
+                                .possible_values(&[
+                                    "posix",
+                                    "fish",
+                                    "powershell",
+                                    "json",
+                                    "yaml",
+                                    "docker",
+                                    "systemd",
+                                ])
+                                .default_value("posix")
+                                .help("The output format"),
+                        ),
+                ),
+        )
         .get_matches();
 
-    match matches.value_of("FILE") {
-        None => dotenv_rs::dotenv(),
-        Some(file) => dotenv_rs::from_filename(file),
+    let mut loader = DotenvLoader::new().override_existing(matches.is_present("OVERRIDE"));
+    if let Some(file) = matches.value_of("FILE") {
+        loader = loader.filename(file);
+    }
+    if let Some(mode) = matches.value_of("MODE") {
+        loader = loader.flow(mode);
+    }
+
+    // the commands of the tool itself are kept apart, so as not to shadow any program
+    if let ("tool", Some(matches)) = matches.subcommand() {
+        match matches.subcommand() {
+            ("check", Some(matches)) => check(matches, &loader),
+            ("diff-example", Some(matches)) => diff_example(matches, &loader),
+            ("explain", Some(matches)) => explain(matches, &loader),
+            ("export", Some(matches)) => export(matches, &loader),
+            _ => unreachable!("a subcommand is required"),
+        }
     }
-    .unwrap_or_else(|e| die!("error: failed to load environment: {}", e));
 
     let mut command = match matches.subcommand() {
         (name, Some(matches)) => {
@@ -59,6 +287,21 @@ fn main() {
         _ => die!("error: missing required argument <COMMAND>"),
     };
 
+    // load into the command only, our own environment stays as it was
+    let report = loader
+        .load_into_with_report(&mut command)
+        .unwrap_or_else(|e| die!("error: failed to load environment: {}", e));
+    if matches.is_present("VERBOSE") {
+        for key in &report.keys {
+            eprintln!(
+                "{}: {}=*** ({})",
+                key.outcome,
+                key.key,
+                location(&key.path, key.line)
+            );
+        }
+    }
+
     if cfg!(target_os = "windows") {
         match command.spawn().and_then(|mut child| child.wait()) {
             Ok(status) => exit(status.code().unwrap_or(1)),
@@ -0,0 +1,50 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::errors::*;
+use crate::loader::DotenvLoader;
+
+/// Sets the variables of the file at the specified path in the environment of `command`,
+/// leaving the environment of the current process untouched.
+///
+/// References are substituted from the current process environment, and variables the
+/// command would already inherit or has been given are not replaced.
+///
+/// # Examples
+/// ```no_run
+/// use std::process::Command;
+///
+/// let mut command = Command::new("server");
+/// dotenv_rs::apply_to_command(&mut command, "server.env").unwrap();
+/// command.spawn().unwrap();
+/// ```
+pub fn apply_to_command<P: AsRef<Path>>(command: &mut Command, path: P) -> Result<()> {
+    DotenvLoader::new().path(path).load_into(command)?;
+    Ok(())
+}
+
+/// Extends `std::process::Command` with loading a .env file into its environment.
+pub trait CommandExt {
+    /// Like `apply_to_command`, but can be chained with the other builder methods.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::process::Command;
+    /// use dotenv_rs::CommandExt;
+    ///
+    /// Command::new("server")
+    ///     .env_file("server.env")
+    ///     .unwrap()
+    ///     .arg("--verbose")
+    ///     .spawn()
+    ///     .unwrap();
+    /// ```
+    fn env_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self>;
+}
+
+impl CommandExt for Command {
+    fn env_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        apply_to_command(self, path)?;
+        Ok(self)
+    }
+}
@@ -0,0 +1,402 @@
+use std::env;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::de::value::StrDeserializer;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+
+use crate::errors::*;
+use crate::loader::DotenvLoader;
+
+/// Separates the names of nested structs and their fields, as in `DB__PORT`.
+const NESTING_SEPARATOR: &str = "__";
+
+/// Deserializes the variables of the file at the specified path into `T`, without loading
+/// them into the environment.
+///
+/// See `EnvDeserializer` for how variables map to fields.
+///
+/// # Examples
+/// ```no_run
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     port: u16,
+///     debug: Option<bool>,
+/// }
+///
+/// let config: Config = dotenv_rs::from_path_into(".env").unwrap();
+/// ```
+pub fn from_path_into<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    let vars = DotenvLoader::new().path(path).to_map()?;
+    T::deserialize(EnvDeserializer::new(
+        vars.into_iter()
+            .map(|(key, value)| (key, value.unwrap_or_default())),
+    ))
+}
+
+/// A serde `Deserializer` over a set of environment variables.
+///
+/// Struct fields are matched to variables ignoring case, so `db_url` is read from `DB_URL`,
+/// and `#[serde(rename)]` picks another name. Nested structs read the variables named after
+/// their field, then `__`, then their own fields: `db.port` is read from `DB__PORT`. Numbers
+/// are parsed from their text, bools as by `var_bool`, sequences are comma-separated, and an
+/// empty value deserializes as `None` for `Option` fields.
+pub struct EnvDeserializer {
+    vars: Vec<(String, String)>,
+}
+
+impl EnvDeserializer {
+    pub fn new<I: IntoIterator<Item = (String, String)>>(vars: I) -> Self {
+        EnvDeserializer {
+            vars: vars.into_iter().collect(),
+        }
+    }
+
+    /// Creates a deserializer over the variables of the current process.
+    pub fn from_env() -> Self {
+        EnvDeserializer::new(env::vars())
+    }
+}
+
+impl<'de> de::Deserializer<'de> for EnvDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        Node::root(&self.vars).deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        Node::root(&self.vars).deserialize_struct(name, fields, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// A variable, or a group of variables sharing a `NAME__` prefix.
+struct Node<'a> {
+    vars: &'a [(String, String)],
+    key: String,
+    value: Option<String>,
+}
+
+impl<'a> Node<'a> {
+    fn root(vars: &'a [(String, String)]) -> Self {
+        Node {
+            vars,
+            key: String::new(),
+            value: None,
+        }
+    }
+
+    fn child(&self, name: &str) -> Self {
+        let key = if self.key.is_empty() {
+            name.to_uppercase()
+        } else {
+            format!("{}{}{}", self.key, NESTING_SEPARATOR, name.to_uppercase())
+        };
+        match self
+            .vars
+            .iter()
+            .find(|(var, _)| var.eq_ignore_ascii_case(&key))
+        {
+            // keep the name as written in the file, for error messages
+            Some((var, value)) => Node {
+                vars: self.vars,
+                key: var.clone(),
+                value: Some(value.clone()),
+            },
+            None => Node {
+                vars: self.vars,
+                key,
+                value: None,
+            },
+        }
+    }
+
+    /// The variables below this node, with the name relative to it.
+    fn children(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let prefix = if self.key.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", self.key, NESTING_SEPARATOR)
+        };
+        self.vars.iter().filter_map(move |(var, value)| {
+            if var.len() > prefix.len()
+                && var.is_char_boundary(prefix.len())
+                && var[..prefix.len()].eq_ignore_ascii_case(&prefix)
+            {
+                Some((&var[prefix.len()..], value.as_str()))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn exists(&self) -> bool {
+        self.value.is_some() || self.children().next().is_some()
+    }
+
+    fn error<T: Display>(&self, message: T) -> Error {
+        Error::Deserialize {
+            key: Some(self.key.clone()),
+            message: message.to_string(),
+        }
+    }
+
+    fn value(&self) -> Result<&str> {
+        self.value
+            .as_deref()
+            .ok_or_else(|| self.error("variable not set"))
+    }
+
+    fn parse<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.value()?.trim().parse().map_err(|err| self.error(err))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Node<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match &self.value {
+            Some(value) => visitor.visit_string(value.clone()),
+            None if self.key.is_empty() || self.exists() => self.deserialize_map(visitor),
+            None => Err(self.error("variable not set")),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // as for `var_bool`, `yes`, `on` and `1` are true too
+        let value = crate::parse_bool(self.value()?).map_err(|err| self.error(err))?;
+        visitor.visit_bool(value)
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_str(self.value()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_bytes(self.value()?.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match &self.value {
+            Some(value) if !value.is_empty() => visitor.visit_some(self),
+            None if self.children().next().is_some() => visitor.visit_some(self),
+            _ => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let value = self.value()?;
+        let items = if value.trim().is_empty() {
+            Vec::new()
+        } else {
+            value
+                .split(',')
+                .map(|item| Node {
+                    vars: self.vars,
+                    key: self.key.clone(),
+                    value: Some(item.trim().to_owned()),
+                })
+                .collect()
+        };
+        visitor.visit_seq(de::value::SeqDeserializer::new(items.into_iter()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let entries: Vec<(&str, Node)> = self
+            .children()
+            .map(|(name, _)| (name, self.child(name)))
+            .collect();
+        visitor.visit_map(MapAccess::new(entries))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let entries: Vec<(&str, Node)> = fields
+            .iter()
+            .map(|field| (*field, self.child(field)))
+            .filter(|(_, node)| node.exists())
+            .collect();
+        visitor
+            .visit_map(MapAccess::new(entries))
+            .map_err(|err| err.with_key(&self.key))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let value = self.value()?.to_owned();
+        visitor
+            .visit_enum(value.into_deserializer())
+            .map_err(|err: Error| err.with_key(&self.key))
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, Error> for Node<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+struct MapAccess<'a> {
+    entries: std::vec::IntoIter<(&'a str, Node<'a>)>,
+    value: Option<Node<'a>>,
+}
+
+impl<'a> MapAccess<'a> {
+    fn new(entries: Vec<(&'a str, Node<'a>)>) -> Self {
+        MapAccess {
+            entries: entries.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapAccess<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.entries.next() {
+            Some((name, node)) => {
+                self.value = Some(node);
+                let name: StrDeserializer<Error> = name.into_deserializer();
+                seed.deserialize(name).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let node = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        let key = node.key.clone();
+        seed.deserialize(node).map_err(|err| err.with_key(&key))
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Deserialize {
+            key: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl Error {
+    /// Attaches the name of the variable being deserialized, unless a more precise one is
+    /// already known.
+    fn with_key(self, key: &str) -> Self {
+        match self {
+            Error::Deserialize { key: None, message } if !key.is_empty() => Error::Deserialize {
+                key: Some(key.to_owned()),
+                message,
+            },
+            err => err,
+        }
+    }
+}
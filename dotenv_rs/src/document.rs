@@ -0,0 +1,455 @@
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::errors::*;
+use crate::parse;
+
+/// A .env file kept line by line, for editing it without losing its layout.
+///
+/// Blank lines, comments and untouched entries are written back exactly as they were read,
+/// line endings included. Values are handled as the text written after the `=`, quotes and
+/// escapes included: `get` returns it untouched and `set` writes it as given, so
+/// `set("PATH", "$HOME/bin")` writes a reference and `set("PATH", "'$HOME/bin'")` a literal.
+/// `to_string` quotes literal values.
+///
+/// # Examples
+/// ```no_run
+/// use dotenv_rs::Document;
+///
+/// let mut document = Document::from_path(".env").unwrap();
+/// document.set("PORT", "8080");
+/// document.rename_key("DB", "DATABASE_URL");
+/// std::fs::write(".env", document.to_string()).unwrap();
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Document {
+    lines: Vec<DocumentLine>,
+}
+
+/// A line of a `Document`. An entry whose quoted value spans several lines is a single
+/// `DocumentLine`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DocumentLine {
+    /// An empty or whitespace-only line.
+    Blank(String),
+    Comment(String),
+    /// An `# @include PATH` or `source PATH` directive.
+    Include(String),
+    Entry(DocumentEntry),
+}
+
+impl DocumentLine {
+    /// Returns the text of the line as it will be written, line ending included.
+    pub fn raw(&self) -> &str {
+        match self {
+            DocumentLine::Blank(raw) | DocumentLine::Comment(raw) | DocumentLine::Include(raw) => {
+                raw
+            }
+            DocumentLine::Entry(entry) => &entry.raw,
+        }
+    }
+}
+
+/// A `KEY=value` line of a `Document`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentEntry {
+    raw: String,
+    /// The entry without its line ending, physical lines joined with `\n`.
+    text: String,
+    ending: String,
+    key: String,
+    key_span: Range<usize>,
+    value_span: Range<usize>,
+    value: String,
+}
+
+impl DocumentEntry {
+    fn parse(raw: String, line_number: usize) -> Result<DocumentEntry> {
+        let text = entry_text(&raw);
+        let ending = split_ending(&raw).1.to_owned();
+        let entry = match parse::parse_entry(&text) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => unreachable!("blank lines and comments are not entries"),
+            Err(Error::Parse(mut err)) => {
+                err.line += line_number - 1;
+                return Err(Error::Parse(err));
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(DocumentEntry {
+            value: text[entry.value_span.clone()].to_owned(),
+            raw,
+            text,
+            ending,
+            key: entry.key,
+            key_span: entry.key_span,
+            value_span: entry.value_span,
+        })
+    }
+
+    fn new(key: &str, value: &str) -> DocumentEntry {
+        let text = format!("{}={}", key, value);
+        DocumentEntry {
+            raw: format!("{}\n", text),
+            key_span: 0..key.len(),
+            value_span: key.len() + 1..text.len(),
+            text,
+            ending: String::from("\n"),
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the value as written, quotes and escapes included.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns the entry as it will be written, line ending included.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    fn set_value(&mut self, value: &str) {
+        let mut written = value.to_owned();
+        if self.value_span.is_empty() && !self.text[self.value_span.end..].is_empty() {
+            // keep a comment right after the `=` from becoming part of the value
+            written.push(' ');
+        }
+        self.replace(self.value_span.clone(), &written);
+        self.value = value.to_owned();
+    }
+
+    fn set_key(&mut self, key: &str) {
+        self.replace(self.key_span.clone(), key);
+        self.key = key.to_owned();
+    }
+
+    fn replace(&mut self, span: Range<usize>, with: &str) {
+        let shift = |offset: usize| {
+            if offset >= span.end {
+                offset - span.len() + with.len()
+            } else {
+                offset
+            }
+        };
+        self.key_span = shift(self.key_span.start)..shift(self.key_span.end);
+        self.value_span = shift(self.value_span.start)..shift(self.value_span.end);
+        self.text.replace_range(span, with);
+        self.raw = format!("{}{}", self.text, self.ending);
+    }
+}
+
+impl Document {
+    pub fn new() -> Document {
+        Document::default()
+    }
+
+    /// Parses the contents of a .env file.
+    pub fn parse(input: &str) -> Result<Document> {
+        let mut lines = Vec::new();
+        for (line_number, raw) in logical_lines(input) {
+            let trimmed = raw.trim_start();
+            lines.push(if trimmed.trim_end().is_empty() {
+                DocumentLine::Blank(raw)
+            } else if parse::include_directive(&raw).is_some() {
+                DocumentLine::Include(raw)
+            } else if trimmed.starts_with('#') {
+                DocumentLine::Comment(raw)
+            } else {
+                DocumentLine::Entry(DocumentEntry::parse(raw, line_number)?)
+            });
+        }
+        Ok(Document { lines })
+    }
+
+    /// Reads and parses the file at the specified path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Document> {
+        let path = path.as_ref();
+        let input = fs::read_to_string(path).map_err(Error::Io)?;
+        Document::parse(&input).map_err(|err| match err {
+            Error::Parse(mut err) => {
+                err.path = Some(path.to_path_buf());
+                Error::Parse(err)
+            }
+            err => err,
+        })
+    }
+
+    pub fn lines(&self) -> &[DocumentLine] {
+        &self.lines
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &DocumentEntry> {
+        self.lines.iter().filter_map(|line| match line {
+            DocumentLine::Entry(entry) => Some(entry),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the last definition of `key`. Note that loading the file keeps
+    /// the first definition instead, unless overriding: the later ones are skipped as
+    /// already set.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|entry| entry.key == key)
+            .last()
+            .map(DocumentEntry::value)
+    }
+
+    /// Sets the value of `key` to `value` as written, keeping the rest of its line. A new key
+    /// is appended at the end of the document.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.position(key) {
+            Some(index) => self.entry_mut(index).set_value(value),
+            None => self.push(DocumentEntry::new(key, value)),
+        }
+    }
+
+    /// Removes every definition of `key`, returning the value of the last one.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let mut removed = None;
+        self.lines.retain(|line| match line {
+            DocumentLine::Entry(entry) if entry.key == key => {
+                removed = Some(entry.value.clone());
+                false
+            }
+            _ => true,
+        });
+        removed
+    }
+
+    /// Inserts `key` right after the last definition of `after`, moving it there if it is
+    /// already defined. Returns `false`, leaving the document untouched, if `after` is not
+    /// defined.
+    pub fn insert_after(&mut self, after: &str, key: &str, value: &str) -> bool {
+        if self.position(after).is_none() {
+            return false;
+        }
+        if key != after {
+            self.remove(key);
+        }
+        let index = self.position(after).unwrap();
+        self.ensure_ending(index);
+        self.lines.insert(
+            index + 1,
+            DocumentLine::Entry(DocumentEntry::new(key, value)),
+        );
+        true
+    }
+
+    /// Renames every definition of `old` to `new`, keeping their values and layout. Returns
+    /// `false` if `old` is not defined.
+    pub fn rename_key(&mut self, old: &str, new: &str) -> bool {
+        let mut renamed = false;
+        for line in &mut self.lines {
+            if let DocumentLine::Entry(entry) = line {
+                if entry.key == old {
+                    entry.set_key(new);
+                    renamed = true;
+                }
+            }
+        }
+        renamed
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .rposition(|line| matches!(line, DocumentLine::Entry(entry) if entry.key == key))
+    }
+
+    fn entry_mut(&mut self, index: usize) -> &mut DocumentEntry {
+        match &mut self.lines[index] {
+            DocumentLine::Entry(entry) => entry,
+            _ => unreachable!("position only returns entries"),
+        }
+    }
+
+    fn push(&mut self, entry: DocumentEntry) {
+        if let Some(last) = self.lines.len().checked_sub(1) {
+            self.ensure_ending(last);
+        }
+        self.lines.push(DocumentLine::Entry(entry));
+    }
+
+    /// Terminates the line at `index`, so that another one can follow it.
+    fn ensure_ending(&mut self, index: usize) {
+        match &mut self.lines[index] {
+            DocumentLine::Blank(raw) | DocumentLine::Comment(raw) if !raw.ends_with('\n') => {
+                raw.push('\n')
+            }
+            DocumentLine::Entry(entry) if entry.ending.is_empty() => {
+                entry.ending.push('\n');
+                entry.raw.push('\n');
+            }
+            _ => {}
+        }
+    }
+}
+
+impl FromStr for Document {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Document> {
+        Document::parse(input)
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            fmt.write_str(line.raw())?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits `input` into lines, along with the number of the line each one starts on. Line
+/// endings are kept, and the physical lines of a quoted value spanning several of them make
+/// a single line.
+pub(crate) fn logical_lines(input: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    let mut physical_lines = input.split_inclusive('\n');
+    let mut line_number = 0;
+    std::iter::from_fn(move || {
+        let line = physical_lines.next()?;
+        line_number += 1;
+        let start = line_number;
+        let mut raw = line.to_owned();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('#') {
+            return Some((start, raw));
+        }
+        while parse::needs_continuation(split_ending(&raw).0) {
+            match physical_lines.next() {
+                Some(line) => {
+                    raw.push_str(line);
+                    line_number += 1;
+                }
+                // let the parser report the unterminated quote
+                None => break,
+            }
+        }
+        Some((start, raw))
+    })
+}
+
+/// Returns the text of an entry as the parser reads it: without the line ending, and with
+/// the physical lines joined with `\n`.
+pub(crate) fn entry_text(raw: &str) -> String {
+    split_ending(raw)
+        .0
+        .split("\r\n")
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits the line ending, if any, off `line`.
+fn split_ending(line: &str) -> (&str, &str) {
+    let text = line
+        .strip_suffix('\n')
+        .map_or(line, |text| text.strip_suffix('\r').unwrap_or(text));
+    line.split_at(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "# database\r
+export DB_HOST = 'localhost'  # the host\r
+DB_PORT=5432
+
+KEY=\"multi
+line\"
+EMPTY=
+";
+
+    #[test]
+    fn test_round_trip() {
+        let document = Document::parse(INPUT).unwrap();
+        assert_eq!(document.to_string(), INPUT);
+        assert_eq!(document.lines().len(), 6);
+        assert_eq!(document.get("DB_HOST"), Some("'localhost'"));
+        assert_eq!(document.get("KEY"), Some("\"multi\nline\""));
+        assert_eq!(document.get("EMPTY"), Some(""));
+        assert_eq!(document.get("MISSING"), None);
+
+        let document = Document::parse("A=1\nB=2").unwrap();
+        assert_eq!(document.to_string(), "A=1\nB=2");
+
+        let input = "# @include base.env\nsource 'local.env'\nsource=1\n";
+        let document = Document::parse(input).unwrap();
+        assert_eq!(document.to_string(), input);
+        assert!(matches!(document.lines()[1], DocumentLine::Include(_)));
+        assert_eq!(document.get("source"), Some("1"));
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut document = Document::parse(INPUT).unwrap();
+        document.set("DB_HOST", "db.local");
+        document.set("DB_PORT", "'a b'");
+        document.set("NEW", "\"it's \\$HOME\"");
+        assert!(document.rename_key("KEY", "TEXT"));
+        assert!(!document.rename_key("MISSING", "OTHER"));
+        assert!(document.insert_after("DB_PORT", "DB_USER", "admin"));
+        assert!(!document.insert_after("MISSING", "OTHER", "value"));
+        assert_eq!(document.remove("EMPTY"), Some(String::new()));
+        assert_eq!(document.remove("EMPTY"), None);
+
+        assert_eq!(
+            document.to_string(),
+            "# database\r
+export DB_HOST = db.local  # the host\r
+DB_PORT='a b'
+DB_USER=admin
+
+TEXT=\"multi
+line\"
+NEW=\"it's \\$HOME\"
+"
+        );
+        let reparsed = Document::parse(&document.to_string()).unwrap();
+        assert_eq!(reparsed, document);
+        assert_eq!(reparsed.get("DB_PORT"), Some("'a b'"));
+        assert_eq!(reparsed.get("NEW"), Some("\"it's \\$HOME\""));
+    }
+
+    #[test]
+    fn test_references() {
+        let mut document = Document::parse("P=$HOME/bin\nQ=\\$HOME\n").unwrap();
+        assert_eq!(document.get("P"), Some("$HOME/bin"));
+        assert_eq!(document.get("Q"), Some("\\$HOME"));
+
+        let original = document.clone();
+        for key in &["P", "Q"] {
+            let value = document.get(key).unwrap().to_owned();
+            document.set(key, &value);
+        }
+        assert_eq!(document, original);
+
+        document.set("P", "'$HOME/bin'");
+        document.set("R", "${P}");
+        assert_eq!(document.to_string(), "P='$HOME/bin'\nQ=\\$HOME\nR=${P}\n");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = Document::parse("A=1\nB=\"open\n\nC=2\n").unwrap_err();
+        match err {
+            Error::Parse(err) => {
+                assert_eq!(err.line, 2);
+                assert_eq!(err.kind, ParseErrorKind::UnterminatedQuote);
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+}
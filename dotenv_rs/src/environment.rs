@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsStr;
+use std::process::Command;
+
+/// Somewhere variables can be read from, such as the process environment.
+///
+/// Substitutions read the environment through this trait, see `DotenvLoader::source`.
+pub trait EnvSource {
+    /// Returns the value of the variable `key`, or `None` if it is not set or not valid
+    /// unicode.
+    fn get(&self, key: &str) -> Option<String>;
+}
+
+/// Somewhere variables can be loaded into, such as the process environment or the environment
+/// of a child process.
+///
+/// A sink is also a source: loading without overriding checks it for variables that are
+/// already set. See `DotenvLoader::load_into`.
+pub trait EnvSink: EnvSource {
+    fn set(&mut self, key: &str, value: &str);
+}
+
+/// The environment of the current process.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessEnv;
+
+impl EnvSource for ProcessEnv {
+    fn get(&self, key: &str) -> Option<String> {
+        env::var(key).ok()
+    }
+}
+
+impl EnvSink for ProcessEnv {
+    fn set(&mut self, key: &str, value: &str) {
+        env::set_var(key, value);
+    }
+}
+
+impl EnvSource for HashMap<String, String> {
+    fn get(&self, key: &str) -> Option<String> {
+        HashMap::get(self, key).cloned()
+    }
+}
+
+impl EnvSink for HashMap<String, String> {
+    fn set(&mut self, key: &str, value: &str) {
+        self.insert(key.to_owned(), value.to_owned());
+    }
+}
+
+/// Reads the environment the command would run with: the variables set or removed on it,
+/// then the ones it inherits from the current process. Does not account for `env_clear`.
+impl EnvSource for Command {
+    fn get(&self, key: &str) -> Option<String> {
+        match self.get_envs().find(|(name, _)| *name == OsStr::new(key)) {
+            Some((_, value)) => value.and_then(|value| value.to_str().map(str::to_owned)),
+            None => env::var(key).ok(),
+        }
+    }
+}
+
+impl EnvSink for Command {
+    fn set(&mut self, key: &str, value: &str) {
+        self.env(key, value);
+    }
+}
+
+impl<T: EnvSource + ?Sized> EnvSource for &T {
+    fn get(&self, key: &str) -> Option<String> {
+        (**self).get(key)
+    }
+}
+
+impl<T: EnvSource + ?Sized> EnvSource for &mut T {
+    fn get(&self, key: &str) -> Option<String> {
+        (**self).get(key)
+    }
+}
+
+impl<T: EnvSink + ?Sized> EnvSink for &mut T {
+    fn set(&mut self, key: &str, value: &str) {
+        (**self).set(key, value)
+    }
+}
+
+/// Where substitutions look a referenced variable up: in the environment, that is the
+/// `EnvSource` of the loader, or in the variables defined in the files.
+///
+/// See `DotenvLoader::substitution_policy`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SubstitutionPolicy {
+    /// The environment, then the files.
+    #[default]
+    EnvFirst,
+    /// The files, then the environment.
+    FileFirst,
+    /// Only the files. The environment is never read, as hermetic builds need.
+    FileOnly,
+    /// Only the environment.
+    EnvOnly,
+}
+
+impl SubstitutionPolicy {
+    /// Tells whether the environment is read at all.
+    pub(crate) fn reads_env(self) -> bool {
+        self != SubstitutionPolicy::FileOnly
+    }
+
+    /// Tells whether the variables of the files are read at all.
+    pub(crate) fn reads_files(self) -> bool {
+        self != SubstitutionPolicy::EnvOnly
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_map() {
+        let mut map = HashMap::new();
+        map.set("KEY", "value");
+        assert_eq!(EnvSource::get(&map, "KEY"), Some("value".to_owned()));
+        assert_eq!(EnvSource::get(&map, "MISSING"), None);
+    }
+
+    #[test]
+    fn test_command() {
+        env::set_var("ENVIRONMENT_INHERITED", "parent");
+        env::set_var("ENVIRONMENT_REMOVED", "parent");
+        let mut command = Command::new("true");
+        command.env_remove("ENVIRONMENT_REMOVED");
+        command.set("ENVIRONMENT_SET", "child");
+
+        assert_eq!(command.get("ENVIRONMENT_SET"), Some("child".to_owned()));
+        assert_eq!(
+            command.get("ENVIRONMENT_INHERITED"),
+            Some("parent".to_owned())
+        );
+        assert_eq!(command.get("ENVIRONMENT_REMOVED"), None);
+        assert_eq!(env::var("ENVIRONMENT_SET").ok(), None);
+    }
+}
@@ -1,14 +1,86 @@
 use std::error;
 use std::fmt;
 use std::io;
+use std::path::PathBuf;
+
+#[cfg(any(feature = "json", feature = "yaml", feature = "toml"))]
+use crate::format::Format;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
+#[allow(clippy::manual_non_exhaustive)]
 pub enum Error {
+    /// Kept for compatibility, parse errors are now reported as `Error::Parse`.
+    #[deprecated(note = "parse errors are reported as `Error::Parse`")]
     LineParse(String, usize),
+    Parse(ParseError),
     Io(io::Error),
     EnvVar(std::env::VarError),
+    /// A `${NAME?message}` or `${NAME:?message}` substitution found `NAME` unset (or empty).
+    /// `path` and `line` locate the entry, when reading a file.
+    UnsetVariable {
+        name: String,
+        message: String,
+        path: Option<PathBuf>,
+        line: Option<usize>,
+    },
+    /// `key` cannot be written as a variable name in a .env file. `path` and `line` locate
+    /// the variable, when reading a file.
+    InvalidKey {
+        key: String,
+        path: Option<PathBuf>,
+        line: Option<usize>,
+    },
+    /// Variables listed in an example file are neither in the loaded files nor set.
+    MissingRequired {
+        keys: Vec<String>,
+    },
+    /// The value of the variable `key` could not be converted to the requested type.
+    InvalidValue {
+        key: String,
+        message: String,
+    },
+    /// A variable could not be converted to the requested type. `key` names the variable,
+    /// when the failure is tied to one.
+    #[cfg(feature = "serde")]
+    Deserialize {
+        key: Option<String>,
+        message: String,
+    },
+    /// An annotation of a schema file is not valid.
+    #[cfg(feature = "schema")]
+    InvalidSchema {
+        line: usize,
+        message: String,
+    },
+    /// Variables refer to each other in a loop, when resolving forward references. `cycle`
+    /// lists the variables from the first one back to it; `path` and `line` locate the entry
+    /// of the first one.
+    ReferenceCycle {
+        cycle: Vec<String>,
+        path: Option<PathBuf>,
+        line: Option<usize>,
+    },
+    /// An include directive names a file that is already being read. `chain` lists the files
+    /// from the outermost one to the one included again.
+    IncludeCycle {
+        chain: Vec<PathBuf>,
+    },
+    /// `error` happened in a file pulled in by include directives. `chain` lists the file and
+    /// line of each directive that led there, outermost first; the path is `None` for a reader
+    /// that is not a file.
+    Include {
+        chain: Vec<(Option<PathBuf>, usize)>,
+        error: Box<Error>,
+    },
+    /// A JSON, YAML or TOML file cannot be parsed, or its top level is not an object.
+    #[cfg(any(feature = "json", feature = "yaml", feature = "toml"))]
+    InvalidDocument {
+        format: Format,
+        path: Option<PathBuf>,
+        message: String,
+    },
     #[doc(hidden)]
     __Nonexhaustive,
 }
@@ -20,13 +92,54 @@ impl Error {
         }
         false
     }
+
+    /// Adds the position of the entry starting on `line` of the file at `path` to an error
+    /// found in it.
+    pub(crate) fn located(self, path: Option<&PathBuf>, line: usize) -> Error {
+        match self {
+            Error::Parse(mut err) => {
+                // the parser counts lines from the start of the entry
+                err.line += line - 1;
+                err.path = path.cloned();
+                Error::Parse(err)
+            }
+            Error::UnsetVariable { name, message, .. } => Error::UnsetVariable {
+                name,
+                message,
+                path: path.cloned(),
+                line: Some(line),
+            },
+            Error::InvalidKey { key, .. } => Error::InvalidKey {
+                key,
+                path: path.cloned(),
+                line: Some(line),
+            },
+            Error::ReferenceCycle { cycle, .. } => Error::ReferenceCycle {
+                cycle,
+                path: path.cloned(),
+                line: Some(line),
+            },
+            err => err,
+        }
+    }
+}
+
+/// Renders the position of an error, if known, as a prefix to its message.
+fn position(path: &Option<PathBuf>, line: &Option<usize>) -> String {
+    match (path, line) {
+        (Some(path), Some(line)) => format!("{}:{}: ", path.display(), line),
+        (None, Some(line)) => format!("line {}: ", line),
+        _ => String::new(),
+    }
 }
 
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
+            Error::Parse(err) => Some(err),
             Error::Io(err) => Some(err),
             Error::EnvVar(err) => Some(err),
+            Error::Include { error, .. } => Some(&**error),
             _ => None,
         }
     }
@@ -37,16 +150,164 @@ impl fmt::Display for Error {
         match self {
             Error::Io(err) => write!(fmt, "{}", err),
             Error::EnvVar(err) => write!(fmt, "{}", err),
+            Error::Parse(err) => write!(fmt, "{}", err),
+            #[allow(deprecated)]
             Error::LineParse(line, error_index) => write!(
                 fmt,
                 "Error parsing line: '{}', error at line index: {}",
                 line, error_index
             ),
+            Error::UnsetVariable {
+                name,
+                message,
+                path,
+                line,
+            } => write!(fmt, "{}{}: {}", position(path, line), name, message),
+            Error::InvalidKey { key, path, line } => write!(
+                fmt,
+                "{}Invalid variable name: {:?}",
+                position(path, line),
+                key
+            ),
+            Error::MissingRequired { keys } => {
+                write!(fmt, "Missing required variables: {}", keys.join(", "))
+            }
+            Error::InvalidValue { key, message } => {
+                write!(fmt, "Invalid value for {}: {}", key, message)
+            }
+            Error::ReferenceCycle { cycle, path, line } => write!(
+                fmt,
+                "{}Reference cycle: {}",
+                position(path, line),
+                cycle.join(" -> ")
+            ),
+            Error::IncludeCycle { chain } => {
+                let paths: Vec<String> = chain
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                write!(fmt, "Include cycle: {}", paths.join(" -> "))
+            }
+            Error::Include { chain, error } => {
+                let sites: Vec<String> = chain
+                    .iter()
+                    .map(|(path, line)| match path {
+                        Some(path) => format!("{}:{}", path.display(), line),
+                        None => format!("line {}", line),
+                    })
+                    .collect();
+                write!(fmt, "{} (included from {})", error, sites.join(" -> "))
+            }
+            #[cfg(feature = "serde")]
+            Error::Deserialize { key, message } => match key {
+                Some(key) => write!(fmt, "Error deserializing {}: {}", key, message),
+                None => write!(fmt, "Error deserializing: {}", message),
+            },
+            #[cfg(feature = "schema")]
+            Error::InvalidSchema { line, message } => {
+                write!(fmt, "Invalid schema on line {}: {}", line, message)
+            }
+            #[cfg(any(feature = "json", feature = "yaml", feature = "toml"))]
+            Error::InvalidDocument {
+                format,
+                path,
+                message,
+            } => match path {
+                Some(path) => write!(fmt, "Invalid {} in {}: {}", format, path.display(), message),
+                None => write!(fmt, "Invalid {}: {}", format, message),
+            },
             _ => unreachable!(),
         }
     }
 }
 
+/// The reason a line could not be parsed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The key is missing or contains an invalid character.
+    InvalidKey,
+    /// The key is not followed by `=`.
+    MissingEquals,
+    /// A quoted value is never closed.
+    UnterminatedQuote,
+    /// A `${` substitution is never closed.
+    UnterminatedSubstitution,
+    /// A `${...}` substitution uses an unknown operator.
+    InvalidSubstitution,
+    /// A backslash is followed by a character that cannot be escaped.
+    InvalidEscape,
+    /// Unquoted whitespace in a value is followed by something other than a comment.
+    UnexpectedCharacter,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            ParseErrorKind::InvalidKey => "invalid key",
+            ParseErrorKind::MissingEquals => "missing `=` after the key",
+            ParseErrorKind::UnterminatedQuote => "unterminated quote",
+            ParseErrorKind::UnterminatedSubstitution => "unterminated substitution",
+            ParseErrorKind::InvalidSubstitution => "invalid substitution",
+            ParseErrorKind::InvalidEscape => "invalid escape sequence",
+            ParseErrorKind::UnexpectedCharacter => "unexpected character after the value",
+        })
+    }
+}
+
+/// Where and why a .env file could not be parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    /// The file being parsed, when reading from a file.
+    pub path: Option<PathBuf>,
+    /// The 1-based number of the line holding the error.
+    pub line: usize,
+    /// The 1-based column, counted in characters, of the error within its line.
+    pub column: usize,
+    pub kind: ParseErrorKind,
+    /// The text of the line holding the error.
+    pub text: String,
+}
+
+impl ParseError {
+    /// Creates an error for the byte `offset` of `input`, which may span several lines.
+    pub(crate) fn new(input: &str, offset: usize, kind: ParseErrorKind) -> ParseError {
+        let line_start = input[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |index| offset + index);
+        ParseError {
+            path: None,
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            kind,
+            text: input[line_start..line_end].to_owned(),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(
+                fmt,
+                "Error parsing {}:{}:{}: {}",
+                path.display(),
+                self.line,
+                self.column,
+                self.kind
+            ),
+            None => write!(
+                fmt,
+                "Error parsing line {}, column {}: {}",
+                self.line, self.column, self.kind
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::error::Error as StdError;
@@ -76,11 +337,84 @@ mod test {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_lineparse_error_source() {
         let err = Error::LineParse("test line".to_string(), 2);
         assert!(err.source().is_none());
     }
 
+    #[test]
+    fn test_parse_error_source() {
+        let err = Error::Parse(ParseError::new(
+            "test line",
+            5,
+            ParseErrorKind::MissingEquals,
+        ));
+        let parse_err = err.source().unwrap().downcast_ref::<ParseError>().unwrap();
+        assert_eq!(ParseErrorKind::MissingEquals, parse_err.kind);
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let err = ParseError::new(
+            "KEY=\"a\nbé c\"\nnext",
+            11,
+            ParseErrorKind::UnterminatedQuote,
+        );
+        assert_eq!(2, err.line);
+        assert_eq!(4, err.column);
+        assert_eq!("bé c\"", err.text);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_lineparse_error_display() {
+        let err = Error::LineParse("test line".to_string(), 2);
+        let err_desc = format!("{}", err);
+        assert_eq!(
+            "Error parsing line: 'test line', error at line index: 2",
+            err_desc
+        );
+    }
+
+    #[test]
+    fn test_reference_cycle_display() {
+        let err = Error::ReferenceCycle {
+            cycle: vec!["A".to_string(), "B".to_string(), "A".to_string()],
+            path: None,
+            line: None,
+        };
+        assert_eq!(err.to_string(), "Reference cycle: A -> B -> A");
+
+        let err = err.located(Some(&PathBuf::from(".env")), 3);
+        assert_eq!(err.to_string(), ".env:3: Reference cycle: A -> B -> A");
+    }
+
+    #[test]
+    fn test_include_display() {
+        let err = Error::IncludeCycle {
+            chain: vec![
+                PathBuf::from(".env"),
+                PathBuf::from("base.env"),
+                PathBuf::from(".env"),
+            ],
+        };
+        assert_eq!(err.to_string(), "Include cycle: .env -> base.env -> .env");
+
+        let err = Error::Include {
+            chain: vec![(None, 2), (Some(PathBuf::from("base.env")), 5)],
+            error: Box::new(Error::InvalidKey {
+                key: "1KEY".to_string(),
+                path: None,
+                line: None,
+            }),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Invalid variable name: \"1KEY\" (included from line 2 -> base.env:5)"
+        );
+    }
+
     #[test]
     fn test_error_not_found_true() {
         let err = Error::Io(std::io::ErrorKind::NotFound.into());
@@ -114,12 +448,67 @@ mod test {
     }
 
     #[test]
-    fn test_lineparse_error_display() {
-        let err = Error::LineParse("test line".to_string(), 2);
-        let err_desc = format!("{}", err);
+    fn test_parse_error_display() {
+        let mut err = ParseError::new("test line", 5, ParseErrorKind::MissingEquals);
         assert_eq!(
-            "Error parsing line: 'test line', error at line index: 2",
-            err_desc
+            "Error parsing line 1, column 6: missing `=` after the key",
+            format!("{}", Error::Parse(err.clone()))
         );
+
+        err.path = Some(PathBuf::from(".env"));
+        assert_eq!(
+            "Error parsing .env:1:6: missing `=` after the key",
+            format!("{}", Error::Parse(err))
+        );
+    }
+
+    #[test]
+    fn test_invalid_key_error_display() {
+        let err = Error::InvalidKey {
+            key: "1 KEY".to_string(),
+            path: None,
+            line: None,
+        };
+        assert_eq!("Invalid variable name: \"1 KEY\"", format!("{}", err));
+
+        let err = err.located(None, 2);
+        assert_eq!(
+            "line 2: Invalid variable name: \"1 KEY\"",
+            format!("{}", err)
+        );
+    }
+
+    #[test]
+    fn test_missing_required_error_display() {
+        let err = Error::MissingRequired {
+            keys: vec!["DB_URL".to_string(), "PORT".to_string()],
+        };
+        assert_eq!(
+            "Missing required variables: DB_URL, PORT",
+            format!("{}", err)
+        );
+    }
+
+    #[test]
+    fn test_invalid_value_error_display() {
+        let err = Error::InvalidValue {
+            key: "PORT".to_string(),
+            message: "invalid digit found in string".to_string(),
+        };
+        assert_eq!(
+            "Invalid value for PORT: invalid digit found in string",
+            format!("{}", err)
+        );
+    }
+
+    #[test]
+    fn test_unset_variable_error_display() {
+        let err = Error::UnsetVariable {
+            name: "DB_URL".to_string(),
+            message: "must be set".to_string(),
+            path: None,
+            line: None,
+        };
+        assert_eq!("DB_URL: must be set", format!("{}", err));
     }
 }
@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use crate::errors::*;
+use crate::loader::DotenvLoader;
+
+/// How a .env file differs from its example file, as returned by `check_against_example`.
+/// Each list is sorted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExampleReport {
+    /// Keys of the example file the .env file does not define.
+    pub missing: Vec<String>,
+    /// Keys of the .env file the example file does not list.
+    pub extra: Vec<String>,
+    /// Keys of the example file the .env file defines with an empty value.
+    pub empty: Vec<String>,
+}
+
+impl ExampleReport {
+    /// Tells whether every key of the example file has a value. Extra keys are allowed.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.empty.is_empty()
+    }
+}
+
+/// Compares the .env file at `env_path` with the example file at `example_path`, which lists
+/// the required keys, usually with blank values. Neither file is loaded into the environment.
+///
+/// # Examples
+/// ```no_run
+/// let report = dotenv_rs::check_against_example(".env", ".env.example").unwrap();
+/// for key in &report.missing {
+///     eprintln!("missing: {}", key);
+/// }
+/// ```
+pub fn check_against_example<P: AsRef<Path>, Q: AsRef<Path>>(
+    env_path: P,
+    example_path: Q,
+) -> Result<ExampleReport> {
+    let vars = crate::get_vars(env_path)?;
+    let mut required = example_keys(example_path.as_ref())?;
+    required.sort();
+
+    let mut report = ExampleReport::default();
+    for key in &required {
+        match vars.get(key) {
+            None => report.missing.push(key.clone()),
+            Some(value) if value.as_deref().unwrap_or_default().is_empty() => {
+                report.empty.push(key.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    report.extra = vars
+        .keys()
+        .filter(|key| required.binary_search(key).is_err())
+        .cloned()
+        .collect();
+    report.extra.sort();
+    Ok(report)
+}
+
+/// Returns the keys listed in an example file. Its values are not substituted.
+pub(crate) fn example_keys(path: &Path) -> Result<Vec<String>> {
+    let vars = DotenvLoader::new()
+        .path(path)
+        .substitution(false)
+        .to_map()?;
+    Ok(vars.into_keys().collect())
+}
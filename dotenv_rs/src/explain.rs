@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::document::{entry_text, logical_lines};
+use crate::environment::{EnvSink, EnvSource, ProcessEnv, SubstitutionPolicy};
+use crate::errors::*;
+use crate::loader::DotenvLoader;
+use crate::parse::{self, Value};
+use crate::report::{KeyReport, LoadOutcome};
+
+/// Why a variable has the value it would have after loading, as returned by `explain`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Explanation {
+    pub key: String,
+    /// The value the variable ends up with, if any.
+    pub value: Option<String>,
+    pub source: ValueSource,
+    /// The definition of the variable in the files that loading would use, or the last one
+    /// when the environment shadows it.
+    pub definition: Option<Definition>,
+    /// Tells whether the variable is already set in the environment, so that the value from
+    /// the files is not used.
+    pub shadowed: bool,
+    /// The references in the value of the definition, and in turn the references in theirs,
+    /// depth first.
+    pub references: Vec<ReferenceStep>,
+}
+
+/// Where the value of a variable comes from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValueSource {
+    Environment,
+    File,
+    Unset,
+}
+
+impl fmt::Display for ValueSource {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            ValueSource::Environment => "environment",
+            ValueSource::File => "file",
+            ValueSource::Unset => "unset",
+        })
+    }
+}
+
+/// An entry of a .env file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Definition {
+    pub path: Option<PathBuf>,
+    /// The 1-based line the entry starts on.
+    pub line: usize,
+    /// The value, with references expanded.
+    pub value: String,
+}
+
+/// A `$NAME` reference that contributed to the value of the variable explained.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferenceStep {
+    pub name: String,
+    /// 1 for the references in the variable explained, 2 for the references in theirs, and
+    /// so on.
+    pub depth: usize,
+    /// The value the reference expanded to, if it was set.
+    pub value: Option<String>,
+    pub source: ValueSource,
+    /// The entry that defined the reference, when it comes from the files.
+    pub definition: Option<Definition>,
+}
+
+/// Explains the value `key` would have after loading the .env file found from the current
+/// directory, without touching the environment.
+///
+/// # Examples
+/// ```no_run
+/// let explanation = dotenv_rs::explain("DATABASE_URL").unwrap();
+/// println!("{:?} from {}", explanation.value, explanation.source);
+/// ```
+pub fn explain(key: &str) -> Result<Explanation> {
+    DotenvLoader::new().explain(key)
+}
+
+impl DotenvLoader {
+    /// Explains the value `key` would have after loading: the file and line defining it,
+    /// whether the environment shadows it, and the references it was expanded from. Nothing
+    /// is loaded into the environment.
+    pub fn explain(&self, key: &str) -> Result<Explanation> {
+        let mut overlay = Overlay::default();
+        let report = self.load_into_with_report(&mut overlay)?;
+        let env = self.env_source();
+        let mut explainer = Explainer {
+            loader: self,
+            env: &*env,
+            reports: &report.keys,
+            entries: HashMap::new(),
+        };
+
+        let defined = report
+            .keys
+            .iter()
+            .rposition(|report| report.key == key && report.outcome != LoadOutcome::Filtered);
+        let loaded = report.keys.iter().rposition(|report| {
+            report.key == key
+                && matches!(report.outcome, LoadOutcome::Set | LoadOutcome::Overridden)
+        });
+        let shadowed = defined.is_some() && loaded.is_none();
+        let value = overlay.get(key);
+        let source = match (loaded, &value) {
+            (Some(_), _) => ValueSource::File,
+            (None, Some(_)) => ValueSource::Environment,
+            (None, None) => ValueSource::Unset,
+        };
+
+        let mut references = Vec::new();
+        if let Some(index) = loaded.or(defined) {
+            explainer.references(index, 1, &mut references)?;
+        }
+        Ok(Explanation {
+            key: key.to_owned(),
+            value,
+            source,
+            definition: loaded
+                .or(defined)
+                .map(|index| definition(&report.keys[index])),
+            shadowed,
+            references,
+        })
+    }
+}
+
+struct Explainer<'a> {
+    loader: &'a DotenvLoader,
+    env: &'a dyn EnvSource,
+    reports: &'a [KeyReport],
+    /// The parsed entries of each file, by line.
+    entries: HashMap<PathBuf, HashMap<usize, Value>>,
+}
+
+impl<'a> Explainer<'a> {
+    /// Adds the references in the value of the entry reported at `index`.
+    fn references(
+        &mut self,
+        index: usize,
+        depth: usize,
+        steps: &mut Vec<ReferenceStep>,
+    ) -> Result<()> {
+        let report = &self.reports[index];
+        let path = match (&report.path, self.loader.substitutes()) {
+            (Some(path), true) => path,
+            _ => return Ok(()),
+        };
+        let value = match self.value(path, report.line)? {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        let mut names = Vec::new();
+        parse::reference_names(&value, &mut names);
+
+        let policy = self.loader.policy();
+        for name in names {
+            let matches = |report: &KeyReport| self.file_name_matches(report, &name);
+            let earlier = self.reports[..index].iter().rposition(matches);
+            let defined = match earlier {
+                _ if !policy.reads_files() => None,
+                None if self.loader.resolves_forward() => self.reports.iter().rposition(matches),
+                earlier => earlier,
+            };
+            // as when parsing, the policy tells whether the environment comes first
+            let env_value = match policy {
+                SubstitutionPolicy::FileFirst if defined.is_some() => None,
+                policy if policy.reads_env() => self.env.get(&name),
+                _ => None,
+            };
+            if let Some(value) = env_value {
+                steps.push(ReferenceStep {
+                    name,
+                    depth,
+                    value: Some(value),
+                    source: ValueSource::Environment,
+                    definition: None,
+                });
+                continue;
+            }
+            match defined {
+                Some(defined) => {
+                    steps.push(ReferenceStep {
+                        name,
+                        depth,
+                        value: Some(self.reports[defined].value.clone()),
+                        source: ValueSource::File,
+                        definition: Some(definition(&self.reports[defined])),
+                    });
+                    self.references(defined, depth + 1, steps)?;
+                }
+                None => steps.push(ReferenceStep {
+                    name,
+                    depth,
+                    value: None,
+                    source: ValueSource::Unset,
+                    definition: None,
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Tells whether the variable reported is the one named `name` in the files.
+    fn file_name_matches(&self, report: &KeyReport, name: &str) -> bool {
+        match report.outcome {
+            LoadOutcome::Filtered => report.key == name,
+            _ => self.loader.exported_name(name).as_deref() == Some(&report.key),
+        }
+    }
+
+    /// Returns the unexpanded value of the entry starting on `line` of the file at `path`.
+    fn value(&mut self, path: &Path, line: usize) -> Result<Option<Value>> {
+        if !self.entries.contains_key(path) {
+            let input = fs::read_to_string(path).map_err(Error::Io)?;
+            let mut entries = HashMap::new();
+            for (line, raw) in logical_lines(&input) {
+                if let Ok(Some(entry)) = parse::parse_entry(&entry_text(&raw)) {
+                    entries.insert(line, entry.value);
+                }
+            }
+            self.entries.insert(path.to_path_buf(), entries);
+        }
+        Ok(self.entries[path].get(&line).cloned())
+    }
+}
+
+fn definition(report: &KeyReport) -> Definition {
+    Definition {
+        path: report.path.clone(),
+        line: report.line,
+        value: report.value.clone(),
+    }
+}
+
+/// The process environment, with the variables loaded kept aside instead of set.
+#[derive(Default)]
+struct Overlay {
+    vars: HashMap<String, String>,
+}
+
+impl EnvSource for Overlay {
+    fn get(&self, key: &str) -> Option<String> {
+        EnvSource::get(&self.vars, key).or_else(|| ProcessEnv.get(key))
+    }
+}
+
+impl EnvSink for Overlay {
+    fn set(&mut self, key: &str, value: &str) {
+        self.vars.set(key, value);
+    }
+}
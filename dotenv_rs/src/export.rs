@@ -0,0 +1,305 @@
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+use crate::errors::*;
+
+/// A format variables can be exported in with `export_string`, for a shell or another tool.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ExportFormat {
+    /// `export KEY='value'` lines, to be evaluated by a POSIX shell.
+    Posix,
+    /// `set -gx KEY 'value'` lines, to be evaluated by fish.
+    Fish,
+    /// `$env:KEY = 'value'` lines, to be evaluated by PowerShell.
+    PowerShell,
+    /// A JSON object.
+    Json,
+    /// A YAML mapping.
+    Yaml,
+    /// `KEY=value` lines, as read by `docker run --env-file`. Values are taken literally, so
+    /// they cannot span several lines.
+    Docker,
+    /// `KEY="value"` lines, as read by the `EnvironmentFile` option of systemd units.
+    Systemd,
+}
+
+impl ExportFormat {
+    /// Returns the format with the given name, as listed by `name`.
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        match name {
+            "posix" => Some(ExportFormat::Posix),
+            "fish" => Some(ExportFormat::Fish),
+            "powershell" => Some(ExportFormat::PowerShell),
+            "json" => Some(ExportFormat::Json),
+            "yaml" => Some(ExportFormat::Yaml),
+            "docker" => Some(ExportFormat::Docker),
+            "systemd" => Some(ExportFormat::Systemd),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Posix => "posix",
+            ExportFormat::Fish => "fish",
+            ExportFormat::PowerShell => "powershell",
+            ExportFormat::Json => "json",
+            ExportFormat::Yaml => "yaml",
+            ExportFormat::Docker => "docker",
+            ExportFormat::Systemd => "systemd",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.name())
+    }
+}
+
+/// Reads the variables of the file at the specified path, as `get_vars` does, and writes them
+/// in `format`, sorted by name.
+///
+/// # Examples
+/// ```no_run
+/// use dotenv_rs::ExportFormat;
+///
+/// let script = dotenv_rs::export_vars(".env", ExportFormat::Posix).unwrap();
+/// print!("{}", script);
+/// ```
+pub fn export_vars<P: AsRef<Path>>(path: P, format: ExportFormat) -> Result<String> {
+    let mut vars: Vec<(String, String)> = crate::get_vars(path)?
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or_default()))
+        .collect();
+    vars.sort();
+    export_string(vars, format)
+}
+
+/// Writes variables in `format`, escaping each value so that the target reads it back
+/// unchanged.
+///
+/// Fails with `Error::InvalidKey` on a name the target cannot set, which for the shells and
+/// systemd is anything but letters, digits and underscores, and with `Error::InvalidValue` on a
+/// value spanning several lines in the Docker format.
+///
+/// # Examples
+/// ```
+/// use dotenv_rs::ExportFormat;
+///
+/// let vars = vec![("GREETING", "it's $HOME")];
+/// let script = dotenv_rs::export_string(vars, ExportFormat::Posix).unwrap();
+/// assert_eq!(script, "export GREETING='it'\\''s $HOME'\n");
+/// ```
+pub fn export_string<I, K, V>(vars: I, format: ExportFormat) -> Result<String>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut output = Vec::new();
+    export_to_writer(&mut output, vars, format)?;
+    Ok(String::from_utf8(output).expect("the output is made of strings"))
+}
+
+/// Like `export_string`, but writes to `writer`.
+pub fn export_to_writer<W, I, K, V>(mut writer: W, vars: I, format: ExportFormat) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let needs_name = !matches!(format, ExportFormat::Json | ExportFormat::Yaml);
+    let mut lines = Vec::new();
+    for (key, value) in vars {
+        let (key, value) = (key.as_ref(), value.as_ref());
+        if key.is_empty() || needs_name && !is_name(key) {
+            return Err(Error::InvalidKey {
+                key: key.to_owned(),
+                path: None,
+                line: None,
+            });
+        }
+        lines.push(match format {
+            ExportFormat::Posix => format!("export {}={}", key, posix_quote(value)),
+            ExportFormat::Fish => format!("set -gx {} {}", key, fish_quote(value)),
+            ExportFormat::PowerShell => format!("$env:{} = {}", key, powershell_quote(value)),
+            ExportFormat::Json => format!("  {}: {}", json_string(key), json_string(value)),
+            // quoted, so that keys such as `NO` or `NULL` are not read as booleans or null
+            ExportFormat::Yaml => format!("{}: {}", json_string(key), json_string(value)),
+            ExportFormat::Docker if value.contains(['\n', '\r']) => {
+                return Err(Error::InvalidValue {
+                    key: key.to_owned(),
+                    message: "the docker format cannot hold a line break".to_owned(),
+                });
+            }
+            ExportFormat::Docker => format!("{}={}", key, value),
+            ExportFormat::Systemd => format!("{}={}", key, systemd_quote(value)),
+        });
+    }
+
+    let text = match format {
+        ExportFormat::Json if lines.is_empty() => "{}\n".to_owned(),
+        ExportFormat::Json => format!("{{\n{}\n}}\n", lines.join(",\n")),
+        ExportFormat::Yaml if lines.is_empty() => "{}\n".to_owned(),
+        _ => lines.iter().map(|line| format!("{}\n", line)).collect(),
+    };
+    writer.write_all(text.as_bytes()).map_err(Error::Io)
+}
+
+/// Tells whether `key` can be set as is by a shell.
+fn is_name(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Single quotes `value`, closing the quotes around each `'`.
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Single quotes `value`, in which fish reads `\'` and `\\` as escapes.
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Single quotes `value`, in which PowerShell reads `''` as a quote.
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Double quotes `value`, escaping the characters systemd would otherwise interpret. Line
+/// breaks are kept as is, systemd reads them as part of the quoted value.
+fn systemd_quote(value: &str) -> String {
+    let mut output = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' | '\\' | '$' | '`' => {
+                output.push('\\');
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Quotes `text` as a JSON string, which is also a valid YAML double quoted scalar.
+pub(crate) fn json_string(text: &str) -> String {
+    let mut output = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: [(&str, &str); 3] = [
+        ("PLAIN", "value"),
+        ("QUOTES", "it's \"$HOME\"\\"),
+        ("LINES", "a\nb"),
+    ];
+
+    fn export(format: ExportFormat) -> String {
+        export_string(VARS.iter().copied(), format).unwrap()
+    }
+
+    #[test]
+    fn test_shells() {
+        assert_eq!(
+            export(ExportFormat::Posix),
+            "export PLAIN='value'
+export QUOTES='it'\\''s \"$HOME\"\\'
+export LINES='a
+b'
+"
+        );
+        assert_eq!(
+            export(ExportFormat::Fish),
+            "set -gx PLAIN 'value'
+set -gx QUOTES 'it\\'s \"$HOME\"\\\\'
+set -gx LINES 'a
+b'
+"
+        );
+        assert_eq!(
+            export(ExportFormat::PowerShell),
+            "$env:PLAIN = 'value'
+$env:QUOTES = 'it''s \"$HOME\"\\'
+$env:LINES = 'a
+b'
+"
+        );
+    }
+
+    #[test]
+    fn test_data_formats() {
+        assert_eq!(
+            export(ExportFormat::Json),
+            "{
+  \"PLAIN\": \"value\",
+  \"QUOTES\": \"it's \\\"$HOME\\\"\\\\\",
+  \"LINES\": \"a\\nb\"
+}
+"
+        );
+        assert_eq!(
+            export(ExportFormat::Yaml),
+            "\"PLAIN\": \"value\"
+\"QUOTES\": \"it's \\\"$HOME\\\"\\\\\"
+\"LINES\": \"a\\nb\"
+"
+        );
+        assert_eq!(
+            export_string(vec![("NO", "1"), ("NULL", "")], ExportFormat::Yaml).unwrap(),
+            "\"NO\": \"1\"\n\"NULL\": \"\"\n"
+        );
+        let empty: Vec<(&str, &str)> = Vec::new();
+        assert_eq!(export_string(empty, ExportFormat::Json).unwrap(), "{}\n");
+    }
+
+    #[test]
+    fn test_env_files() {
+        assert_eq!(
+            export(ExportFormat::Systemd),
+            "PLAIN=\"value\"
+QUOTES=\"it's \\\"\\$HOME\\\"\\\\\"
+LINES=\"a
+b\"
+"
+        );
+        assert_eq!(
+            export_string(VARS[..2].iter().copied(), ExportFormat::Docker).unwrap(),
+            "PLAIN=value\nQUOTES=it's \"$HOME\"\\\n"
+        );
+        let err = export_string(VARS.iter().copied(), ExportFormat::Docker).unwrap_err();
+        assert!(matches!(err, Error::InvalidValue { key, .. } if key == "LINES"));
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        let vars = vec![("A.B", "1")];
+        let err = export_string(vars.clone(), ExportFormat::Posix).unwrap_err();
+        assert!(matches!(err, Error::InvalidKey { key, .. } if key == "A.B"));
+        assert_eq!(
+            export_string(vars, ExportFormat::Yaml).unwrap(),
+            "\"A.B\": \"1\"\n"
+        );
+    }
+}
@@ -1,18 +1,18 @@
-use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::{env, fs, io};
 
 use crate::errors::*;
-use crate::iter::Iter;
 
 pub struct Finder<'a> {
     filename: &'a Path,
+    directory: Option<&'a Path>,
 }
 
 impl<'a> Finder<'a> {
     pub fn new() -> Self {
         Finder {
             filename: Path::new(".env"),
+            directory: None,
         }
     }
 
@@ -21,11 +21,18 @@ impl<'a> Finder<'a> {
         self
     }
 
-    pub fn find(self) -> Result<(PathBuf, Iter<File>)> {
-        let path = find(&env::current_dir().map_err(Error::Io)?, self.filename)?;
-        let file = File::open(&path).map_err(Error::Io)?;
-        let iter = Iter::new(file);
-        Ok((path, iter))
+    /// Sets the directory the search starts from, instead of the current one.
+    pub fn directory(mut self, directory: &'a Path) -> Self {
+        self.directory = Some(directory);
+        self
+    }
+
+    /// Returns the path of the first matching file.
+    pub fn find(&self) -> Result<PathBuf> {
+        match self.directory {
+            Some(directory) => find(directory, self.filename),
+            None => find(&env::current_dir().map_err(Error::Io)?, self.filename),
+        }
     }
 }
 
@@ -0,0 +1,405 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::errors::*;
+
+/// The syntax of a file read by `Iter` or `DotenvLoader`.
+///
+/// The structured formats are flattened: the keys of nested objects are joined with `__`, so
+/// that `{"database": {"url": "..."}}` defines `database__url`, and the items of arrays are
+/// numbered from 0. Numbers and booleans are written as in the file, and nulls as empty
+/// values. Values are taken literally, without substitution.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Format {
+    /// The `.env` syntax, the default.
+    #[default]
+    Dotenv,
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+    /// Java `.properties` files, whose keys such as `a.b` are kept as is.
+    #[cfg(feature = "properties")]
+    Properties,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            Format::Dotenv => "dotenv",
+            #[cfg(feature = "json")]
+            Format::Json => "JSON",
+            #[cfg(feature = "yaml")]
+            Format::Yaml => "YAML",
+            #[cfg(feature = "toml")]
+            Format::Toml => "TOML",
+            #[cfg(feature = "properties")]
+            Format::Properties => "properties",
+        })
+    }
+}
+
+/// A variable read from a file that is not in the `.env` syntax: its line, or 0 when the
+/// format does not keep track of lines, its name and its value.
+pub(crate) type Converted = (usize, String, String);
+
+/// Reads the variables of `input`, which must not be in the `.env` syntax. Fails with
+/// `Error::InvalidDocument` if `input`, read from `path`, cannot be parsed as a whole.
+#[allow(unused_variables)]
+pub(crate) fn convert(format: Format, input: &str, path: Option<&Path>) -> Result<Vec<Converted>> {
+    match format {
+        Format::Dotenv => unreachable!("the .env syntax is parsed line by line"),
+        #[cfg(feature = "json")]
+        Format::Json => match serde_json::from_str(input) {
+            Ok(value) => flatten_document(format, path, json_node(value)),
+            Err(err) => Err(invalid_document(format, path, err.to_string())),
+        },
+        #[cfg(feature = "yaml")]
+        Format::Yaml => match serde_yaml::from_str(input) {
+            Ok(value) => match yaml_node(value) {
+                Some(node) => flatten_document(format, path, node),
+                None => Err(invalid_document(
+                    format,
+                    path,
+                    "keys must be scalars".to_owned(),
+                )),
+            },
+            Err(err) => Err(invalid_document(format, path, err.to_string())),
+        },
+        #[cfg(feature = "toml")]
+        Format::Toml => match toml::from_str(input) {
+            Ok(table) => flatten_document(format, path, toml_node(toml::Value::Table(table))),
+            Err(err) => Err(invalid_document(format, path, err.to_string())),
+        },
+        #[cfg(feature = "properties")]
+        Format::Properties => Ok(properties(input)),
+    }
+}
+
+#[cfg(any(feature = "json", feature = "yaml", feature = "toml"))]
+fn invalid_document(format: Format, path: Option<&Path>, message: String) -> Error {
+    Error::InvalidDocument {
+        format,
+        path: path.map(Path::to_path_buf),
+        message,
+    }
+}
+
+/// A value of a structured format.
+#[cfg(any(feature = "json", feature = "yaml", feature = "toml"))]
+enum Node {
+    Scalar(String),
+    Table(Vec<(String, Node)>),
+    List(Vec<Node>),
+}
+
+#[cfg(any(feature = "json", feature = "yaml", feature = "toml"))]
+fn flatten_document(format: Format, path: Option<&Path>, node: Node) -> Result<Vec<Converted>> {
+    let mut vars = Vec::new();
+    match node {
+        Node::Table(_) => flatten(node, "", &mut vars),
+        _ => {
+            let message = "the top level must be an object".to_owned();
+            return Err(invalid_document(format, path, message));
+        }
+    }
+    Ok(vars)
+}
+
+#[cfg(any(feature = "json", feature = "yaml", feature = "toml"))]
+fn flatten(node: Node, name: &str, vars: &mut Vec<Converted>) {
+    let join = |key: &str| match name {
+        "" => key.to_owned(),
+        _ => format!("{}__{}", name, key),
+    };
+    match node {
+        Node::Scalar(value) => vars.push((0, name.to_owned(), value)),
+        Node::Table(entries) => {
+            for (key, node) in entries {
+                flatten(node, &join(&key), vars);
+            }
+        }
+        Node::List(items) => {
+            for (index, node) in items.into_iter().enumerate() {
+                flatten(node, &join(&index.to_string()), vars);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+fn json_node(value: serde_json::Value) -> Node {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => Node::Scalar(String::new()),
+        Value::Bool(value) => Node::Scalar(value.to_string()),
+        Value::Number(value) => Node::Scalar(value.to_string()),
+        Value::String(value) => Node::Scalar(value),
+        Value::Array(items) => Node::List(items.into_iter().map(json_node).collect()),
+        Value::Object(entries) => Node::Table(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, json_node(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Returns `None` if a mapping has a key that is not a scalar.
+#[cfg(feature = "yaml")]
+fn yaml_node(value: serde_yaml::Value) -> Option<Node> {
+    use serde_yaml::Value;
+
+    Some(match value {
+        Value::Null => Node::Scalar(String::new()),
+        Value::Bool(value) => Node::Scalar(value.to_string()),
+        Value::Number(value) => Node::Scalar(value.to_string()),
+        Value::String(value) => Node::Scalar(value),
+        Value::Sequence(items) => {
+            Node::List(items.into_iter().map(yaml_node).collect::<Option<_>>()?)
+        }
+        Value::Mapping(entries) => Node::Table(
+            entries
+                .into_iter()
+                .map(|(key, value)| match yaml_node(key)? {
+                    Node::Scalar(key) => Some((key, yaml_node(value)?)),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+        ),
+        Value::Tagged(tagged) => yaml_node(tagged.value)?,
+    })
+}
+
+#[cfg(feature = "toml")]
+fn toml_node(value: toml::Value) -> Node {
+    use toml::Value;
+
+    match value {
+        Value::String(value) => Node::Scalar(value),
+        Value::Integer(value) => Node::Scalar(value.to_string()),
+        Value::Float(value) => Node::Scalar(value.to_string()),
+        Value::Boolean(value) => Node::Scalar(value.to_string()),
+        Value::Datetime(value) => Node::Scalar(value.to_string()),
+        Value::Array(items) => Node::List(items.into_iter().map(toml_node).collect()),
+        Value::Table(entries) => Node::Table(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, toml_node(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Reads the entries of a `.properties` file. A line ending with an odd number of
+/// backslashes continues on the next one, and a key is separated from its value by `=`, `:`
+/// or whitespace, unless escaped with a backslash.
+#[cfg(feature = "properties")]
+fn properties(input: &str) -> Vec<Converted> {
+    let mut vars = Vec::new();
+    let mut lines = input.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with(['#', '!']) {
+            continue;
+        }
+        let mut logical = line.to_owned();
+        while ends_with_escape(&logical) {
+            logical.pop();
+            match lines.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+
+        let mut chars = logical.chars().peekable();
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c == ':' || c.is_whitespace() {
+                break;
+            }
+            chars.next();
+            push_unescaped(c, &mut chars, &mut key);
+        }
+        // whitespace around a single `=` or `:` separator
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if let Some('=') | Some(':') = chars.peek() {
+            chars.next();
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+        }
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            push_unescaped(c, &mut chars, &mut value);
+        }
+        vars.push((index + 1, key, value));
+    }
+    vars
+}
+
+#[cfg(feature = "properties")]
+fn ends_with_escape(line: &str) -> bool {
+    line.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1
+}
+
+/// Pushes `c` to `output`, or the character it escapes along with the following ones.
+#[cfg(feature = "properties")]
+fn push_unescaped<I>(c: char, chars: &mut std::iter::Peekable<I>, output: &mut String)
+where
+    I: Iterator<Item = char>,
+{
+    if c != '\\' {
+        output.push(c);
+        return;
+    }
+    match chars.next() {
+        Some('t') => output.push('\t'),
+        Some('n') => output.push('\n'),
+        Some('r') => output.push('\r'),
+        Some('f') => output.push('\u{c}'),
+        Some('u') => {
+            let digits: String = chars.by_ref().take(4).collect();
+            match u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                Some(c) => output.push(c),
+                None => {
+                    output.push_str("\\u");
+                    output.push_str(&digits);
+                }
+            }
+        }
+        Some(c) => output.push(c),
+        None => {}
+    }
+}
+
+#[cfg(all(
+    test,
+    any(
+        feature = "json",
+        feature = "yaml",
+        feature = "toml",
+        feature = "properties"
+    )
+))]
+mod tests {
+    use super::*;
+
+    fn names(vars: &[Converted]) -> Vec<(&str, &str)> {
+        vars.iter()
+            .map(|(_, key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let vars = convert(
+            Format::Json,
+            r#"{"db": {"url": "postgres://db", "pool": 5, "tls": null}, "hosts": ["a", "b"], "debug": true}"#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            names(&vars),
+            vec![
+                ("db__pool", "5"),
+                ("db__tls", ""),
+                ("db__url", "postgres://db"),
+                ("debug", "true"),
+                ("hosts__0", "a"),
+                ("hosts__1", "b"),
+            ]
+        );
+        assert!(matches!(
+            convert(Format::Json, "[1]", None),
+            Err(Error::InvalidDocument { .. })
+        ));
+        assert!(matches!(
+            convert(Format::Json, "{", None),
+            Err(Error::InvalidDocument { .. })
+        ));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml() {
+        let vars = convert(
+            Format::Yaml,
+            "db:\n  url: postgres://db\n  port: 5432\nhosts:\n  - a\n  - b\n",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            names(&vars),
+            vec![
+                ("db__url", "postgres://db"),
+                ("db__port", "5432"),
+                ("hosts__0", "a"),
+                ("hosts__1", "b"),
+            ]
+        );
+        assert!(matches!(
+            convert(Format::Yaml, "? [a]\n: 1\n", None),
+            Err(Error::InvalidDocument { .. })
+        ));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml() {
+        let vars = convert(
+            Format::Toml,
+            "name = \"app\"\n[db]\nurl = \"postgres://db\"\nratio = 0.5\n",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            names(&vars),
+            vec![
+                ("db__ratio", "0.5"),
+                ("db__url", "postgres://db"),
+                ("name", "app"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "properties")]
+    #[test]
+    fn test_properties() {
+        let input = "# comment
+! also a comment
+a.b=c
+key : spaced value
+bare value
+escaped\\=key=\\u00e9\\tx
+long = one \\
+       two
+
+empty
+";
+        let vars = convert(Format::Properties, input, None).unwrap();
+        assert_eq!(
+            names(&vars),
+            vec![
+                ("a.b", "c"),
+                ("key", "spaced value"),
+                ("bare", "value"),
+                ("escaped=key", "\u{e9}\tx"),
+                ("long", "one two"),
+                ("empty", ""),
+            ]
+        );
+        let lines: Vec<usize> = vars.iter().map(|(line, _, _)| *line).collect();
+        assert_eq!(lines, vec![3, 4, 5, 6, 7, 10]);
+    }
+}
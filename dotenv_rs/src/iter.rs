@@ -1,34 +1,206 @@
-use std::collections::HashMap;
-use std::env;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
 use std::io::prelude::*;
-use std::io::{BufReader, Lines};
+use std::io::{self, BufReader, Lines};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use crate::environment::{EnvSink, EnvSource, ProcessEnv, SubstitutionPolicy};
 use crate::errors::*;
+use crate::format::{self, Converted, Format};
 use crate::parse;
+use crate::report::{KeyReport, LoadOutcome, LoadReport};
+use crate::resolve::{self, Pending};
+
+/// A variable read by `Iter::parse_all`, along with where it was defined.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub value: String,
+    pub path: Option<PathBuf>,
+    /// The 1-based line the entry starts on.
+    pub line: usize,
+}
+
+/// A line `Iter::parse_all` skipped, and why.
+#[derive(Debug)]
+pub struct ParseDiagnostic {
+    pub path: Option<PathBuf>,
+    /// The 1-based line the skipped entry starts on.
+    pub line: usize,
+    pub error: Error,
+}
+
+/// A file being read because of an include directive, along with the position of the
+/// directive to go back to.
+struct Include {
+    lines: Lines<BufReader<File>>,
+    path: Option<PathBuf>,
+    line_number: usize,
+    pending_lines: usize,
+}
 
 pub struct Iter<R> {
-    lines: Lines<BufReader<R>>,
+    lines: Option<Lines<BufReader<R>>>,
+    /// The files being included, innermost last.
+    includes: Vec<Include>,
+    queued: VecDeque<(PathBuf, R)>,
+    path: Option<PathBuf>,
+    line_number: usize,
+    pending_lines: usize,
+    substitute: bool,
+    source: Arc<dyn EnvSource + Send + Sync>,
     substitution_data: HashMap<String, Option<String>>,
+    format: Format,
+    /// The variables of the current file, when it is not in the `.env` syntax.
+    converted: VecDeque<Converted>,
+    policy: SubstitutionPolicy,
+    resolve: bool,
+    /// Every entry, with its position, once read in resolve mode.
+    resolved: Option<VecDeque<Resolved>>,
+    /// Renames the variables read, skipping the ones it returns `None` for.
+    rename: Option<Box<Rename>>,
 }
 
+type Resolved = (Option<PathBuf>, usize, Result<(String, String)>);
+
+type Rename = dyn Fn(&str) -> Option<String> + Send + Sync;
+
 impl<R: Read> Iter<R> {
     pub fn new(reader: R) -> Iter<R> {
+        let mut iter = Iter::from_files(Vec::new());
+        iter.lines = Some(BufReader::new(reader).lines());
+        iter
+    }
+
+    /// Reads the given files one after the other, as if they were a single one: variables
+    /// defined in a file can be substituted in the following ones.
+    pub(crate) fn from_files(files: Vec<(PathBuf, R)>) -> Iter<R> {
         Iter {
-            lines: BufReader::new(reader).lines(),
+            lines: None,
+            includes: Vec::new(),
+            queued: files.into(),
+            path: None,
+            line_number: 0,
+            pending_lines: 0,
+            substitute: true,
+            source: Arc::new(ProcessEnv),
             substitution_data: HashMap::new(),
+            format: Format::Dotenv,
+            converted: VecDeque::new(),
+            policy: SubstitutionPolicy::EnvFirst,
+            resolve: false,
+            resolved: None,
+            rename: None,
         }
     }
 
-    pub fn load(self, prefix: &str) -> Result<()> {
-        for item in self {
+    /// Like `new`, but reads `reader` in the given format.
+    pub fn with_format(reader: R, format: Format) -> Iter<R> {
+        Iter::new(reader).format(format)
+    }
+
+    pub(crate) fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets where references are looked up, the environment first by default.
+    pub(crate) fn policy(mut self, policy: SubstitutionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Renames the variables read with `rename`, skipping the ones it returns `None` for.
+    /// References still use the names in the files.
+    pub(crate) fn rename<F>(mut self, rename: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.rename = Some(Box::new(rename));
+        self
+    }
+
+    /// Turns the resolve mode on or off: when on, all the entries are read before any is
+    /// expanded, so that a reference may name a variable defined further down.
+    pub(crate) fn resolve(mut self, resolve: bool) -> Self {
+        self.resolve = resolve;
+        self
+    }
+
+    /// Turns the expansion of `$VAR` references on or off. When off, values are kept verbatim.
+    pub(crate) fn substitute(mut self, substitute: bool) -> Self {
+        self.substitute = substitute;
+        self
+    }
+
+    /// Sets where references are looked up before the variables of the files. Defaults to
+    /// the process environment.
+    pub(crate) fn source(mut self, source: Arc<dyn EnvSource + Send + Sync>) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Returns the path of the file the most recently read entry comes from, if the iterator
+    /// was created from a file.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the 1-based line number on which the most recently read entry starts.
+    ///
+    /// A quoted value may span several physical lines; the number reported here is
+    /// always the one of the line holding the key. Formats that do not keep track of lines,
+    /// such as JSON, report 0.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Loads the variables starting with `prefix` into the environment, leaving the ones
+    /// already set untouched, and reports what happened to each one.
+    pub fn load(self, prefix: &str) -> Result<LoadReport> {
+        self.load_base(prefix, false)
+    }
+
+    /// Like `load`, but values from the file replace variables already present in the
+    /// environment.
+    pub fn load_override(self, prefix: &str) -> Result<LoadReport> {
+        self.load_base(prefix, true)
+    }
+
+    fn load_base(mut self, prefix: &str, override_existing: bool) -> Result<LoadReport> {
+        let mut sink = ProcessEnv;
+        let mut report = LoadReport::default();
+        while let Some(item) = self.next() {
             let (key, value) = item?;
-            if !key.starts_with(prefix) { continue; }
-            if env::var(&key).is_err() {
-                env::set_var(&key, value);
+            let path = self.path.clone();
+            if let Some(path) = &path {
+                if !report.paths.contains(path) {
+                    report.paths.push(path.clone());
+                }
             }
+            let outcome = if !key.starts_with(prefix) {
+                LoadOutcome::Filtered
+            } else if sink.get(&key).is_none() {
+                LoadOutcome::Set
+            } else if override_existing {
+                LoadOutcome::Overridden
+            } else {
+                LoadOutcome::SkippedExisting
+            };
+            if matches!(outcome, LoadOutcome::Set | LoadOutcome::Overridden) {
+                sink.set(&key, &value);
+            }
+            report.keys.push(KeyReport {
+                key,
+                value,
+                outcome,
+                path,
+                line: self.line_number,
+            });
         }
 
-        Ok(())
+        Ok(report)
     }
 
     pub fn get_vars_base(self, prefix: &str) -> Result<HashMap<String, Option<String>>>{
@@ -47,9 +219,292 @@ impl<R: Read> Iter<R> {
     }
 
     pub fn get_vars(self) -> Result<HashMap<String, Option<String>>> {
-        self.get_vars_base(&String::from(""))
+        self.get_vars_base("")
+    }
+
+    /// Reads all the entries, skipping the invalid ones instead of stopping at the first,
+    /// and returns them along with a diagnostic for each line skipped. Stops early only if
+    /// a file cannot be read.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let (entries, diagnostics) = dotenv_rs::from_filename_iter(".env").unwrap().parse_all();
+    /// for diagnostic in &diagnostics {
+    ///     eprintln!("line {}: {}", diagnostic.line, diagnostic.error);
+    /// }
+    /// ```
+    pub fn parse_all(mut self) -> (Vec<Entry>, Vec<ParseDiagnostic>) {
+        let mut entries = Vec::new();
+        let mut diagnostics = Vec::new();
+        while let Some(item) = self.next() {
+            match item {
+                Ok((key, value)) => entries.push(Entry {
+                    key,
+                    value,
+                    path: self.path.clone(),
+                    line: self.line_number,
+                }),
+                Err(error) => {
+                    let stop = matches!(error, Error::Io(_));
+                    diagnostics.push(ParseDiagnostic {
+                        path: self.path.clone(),
+                        line: self.line_number,
+                        error,
+                    });
+                    if stop {
+                        break;
+                    }
+                }
+            }
+        }
+        (entries, diagnostics)
+    }
+
+    /// Reads physical lines until the quotes opened on the first one are closed.
+    fn next_logical_line(&mut self) -> Option<Result<String>> {
+        let mut buffer = loop {
+            match self.next_physical_line() {
+                Some(Ok(line)) => break line,
+                Some(Err(err)) => return Some(Err(Error::Io(err))),
+                None if !self.includes.is_empty() => self.end_include(),
+                None => {
+                    let (path, reader) = self.queued.pop_front()?;
+                    self.lines = Some(BufReader::new(reader).lines());
+                    self.path = Some(path);
+                    self.line_number = 0;
+                    self.pending_lines = 0;
+                }
+            }
+        };
+        self.line_number += self.pending_lines + 1;
+        self.pending_lines = 0;
+
+        while parse::needs_continuation(&buffer) {
+            match self.next_physical_line() {
+                Some(Ok(line)) => {
+                    buffer.push('\n');
+                    buffer.push_str(&line);
+                    self.pending_lines += 1;
+                }
+                Some(Err(err)) => return Some(Err(Error::Io(err))),
+                // let the parser report the unterminated quote
+                None => break,
+            }
+        }
+
+        Some(Ok(buffer))
+    }
+
+    fn next_physical_line(&mut self) -> Option<io::Result<String>> {
+        match self.includes.last_mut() {
+            Some(include) => include.lines.next(),
+            None => self.lines.as_mut().and_then(Iterator::next),
+        }
     }
 
+    /// Starts reading the file named by the include directive on the current line, relative
+    /// to the directory of the current file.
+    fn start_include(&mut self, target: &str) -> Result<()> {
+        let path = match self.path.as_deref().and_then(Path::parent) {
+            Some(directory) => directory.join(target),
+            None => PathBuf::from(target),
+        };
+        let with_path = |err: io::Error| {
+            Error::Io(io::Error::new(
+                err.kind(),
+                format!("{}: {}", path.display(), err),
+            ))
+        };
+        let canonical = fs::canonicalize(&path).map_err(with_path)?;
+
+        let mut chain: Vec<PathBuf> = self
+            .includes
+            .iter()
+            .filter_map(|include| include.path.clone())
+            .chain(self.path.clone())
+            .collect();
+        if chain
+            .iter()
+            .any(|active| fs::canonicalize(active).ok().as_ref() == Some(&canonical))
+        {
+            chain.push(path);
+            return Err(Error::IncludeCycle { chain });
+        }
+
+        let file = File::open(&path).map_err(with_path)?;
+        self.includes.push(Include {
+            lines: BufReader::new(file).lines(),
+            path: self.path.replace(path),
+            line_number: self.line_number,
+            pending_lines: self.pending_lines,
+        });
+        self.line_number = 0;
+        self.pending_lines = 0;
+        Ok(())
+    }
+
+    /// Goes back to the file holding the innermost include directive.
+    fn end_include(&mut self) {
+        if let Some(include) = self.includes.pop() {
+            self.path = include.path;
+            self.line_number = include.line_number;
+            self.pending_lines = include.pending_lines;
+        }
+    }
+
+    /// Names the include directives that led to `error`, if any.
+    fn in_include(&self, error: Error, directive: Option<usize>) -> Error {
+        let mut chain: Vec<(Option<PathBuf>, usize)> = self
+            .includes
+            .iter()
+            .map(|include| (include.path.clone(), include.line_number))
+            .collect();
+        chain.extend(directive.map(|line| (self.path.clone(), line)));
+        match error {
+            Error::IncludeCycle { .. } => error,
+            _ if chain.is_empty() => error,
+            _ => Error::Include {
+                chain,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Returns the next logical line that is not an include directive, following the
+    /// directives.
+    fn next_entry_line(&mut self) -> Option<Result<String>> {
+        loop {
+            let line = match self.next_logical_line()? {
+                Ok(line) => line,
+                Err(err) => {
+                    let err = self.in_include(err, None);
+                    // stop reading an included file that failed, it may fail again
+                    self.end_include();
+                    return Some(Err(err));
+                }
+            };
+            match parse::include_directive(&line) {
+                Some(target) => {
+                    if let Err(err) = self.start_include(target) {
+                        return Some(Err(self.in_include(err, Some(self.line_number))));
+                    }
+                }
+                None => return Some(Ok(line)),
+            }
+        }
+    }
+
+    /// Adds the position of the current entry to an error found in it.
+    fn locate(&self, err: Error) -> Error {
+        self.in_include(err.located(self.path.as_ref(), self.line_number), None)
+    }
+
+    /// Returns the next entry in resolve mode, reading all of them first.
+    fn next_resolved(&mut self) -> Option<Result<(String, String)>> {
+        if self.resolved.is_none() {
+            let mut positions = Vec::new();
+            let mut pending = Vec::new();
+            let mut errors = Vec::new();
+            while let Some(line) = self.next_entry_line() {
+                let position = (self.path.clone(), self.line_number);
+                let entry =
+                    line.and_then(|line| parse::parse_entry(&line).map_err(|e| self.locate(e)));
+                match entry {
+                    Ok(Some(entry)) => {
+                        positions.push(position);
+                        pending.push(Pending {
+                            empty: entry.value_span.is_empty(),
+                            key: entry.key,
+                            value: entry.value,
+                        });
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        let stop = matches!(err, Error::Io(_));
+                        errors.push((pending.len(), position, err));
+                        if stop {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            let values = resolve::resolve(&pending, &*self.source, self.policy);
+            let mut resolved = VecDeque::new();
+            let mut errors = errors.into_iter().peekable();
+            for (index, ((entry, value), position)) in
+                pending.into_iter().zip(values).zip(positions).enumerate()
+            {
+                while let Some((_, (path, line), err)) =
+                    errors.next_if(|(before, _, _)| *before == index)
+                {
+                    resolved.push_back((path, line, Err(err)));
+                }
+                let (path, line) = position;
+                if let Ok(value) = &value {
+                    self.substitution_data
+                        .insert(entry.key.clone(), Some(value.clone()));
+                }
+                let item = value
+                    .map(|value| (entry.key, value))
+                    .map_err(|err| err.located(path.as_ref(), line));
+                resolved.push_back((path, line, item));
+            }
+            resolved.extend(errors.map(|(_, (path, line), err)| (path, line, Err(err))));
+            self.resolved = Some(resolved);
+        }
+
+        let (path, line, item) = self.resolved.as_mut()?.pop_front()?;
+        self.path = path;
+        self.line_number = line;
+        Some(item)
+    }
+
+    /// Reads the whole of the current file, or else of the next one.
+    fn next_document(&mut self) -> Option<Result<String>> {
+        if self.lines.is_none() {
+            let (path, reader) = self.queued.pop_front()?;
+            self.lines = Some(BufReader::new(reader).lines());
+            self.path = Some(path);
+            self.line_number = 0;
+        }
+        let mut input = String::new();
+        for line in self.lines.take()? {
+            match line {
+                Ok(line) => {
+                    input.push_str(&line);
+                    input.push('\n');
+                }
+                Err(err) => return Some(Err(Error::Io(err))),
+            }
+        }
+        Some(Ok(input))
+    }
+
+    /// Returns the next variable of a file that is not in the `.env` syntax.
+    fn next_converted(&mut self) -> Option<Result<(String, String)>> {
+        loop {
+            if let Some((line, key, value)) = self.converted.pop_front() {
+                self.line_number = line;
+                if !parse::is_valid_key(&key) {
+                    return Some(Err(self.locate(Error::InvalidKey {
+                        key,
+                        path: None,
+                        line: None,
+                    })));
+                }
+                return Some(Ok((key, value)));
+            }
+            let input = match self.next_document()? {
+                Ok(input) => input,
+                Err(err) => return Some(Err(err)),
+            };
+            match format::convert(self.format, &input, self.path.as_deref()) {
+                Ok(vars) => self.converted = vars.into(),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
 }
 
 impl<R: Read> Iterator for Iter<R> {
@@ -57,16 +512,42 @@ impl<R: Read> Iterator for Iter<R> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let line = match self.lines.next() {
-                Some(Ok(line)) => line,
-                Some(Err(err)) => return Some(Err(Error::Io(err))),
-                None => return None,
-            };
+            match (self.next_variable()?, &self.rename) {
+                (Ok((key, value)), Some(rename)) => {
+                    if let Some(key) = rename(&key) {
+                        return Some(Ok((key, value)));
+                    }
+                }
+                (item, _) => return Some(item),
+            }
+        }
+    }
+}
 
-            match parse::parse_line(&line, &mut self.substitution_data) {
+impl<R: Read> Iter<R> {
+    /// Returns the next variable, under its name in the files.
+    fn next_variable(&mut self) -> Option<Result<(String, String)>> {
+        if self.format != Format::Dotenv {
+            return self.next_converted();
+        }
+        if self.resolve && self.substitute {
+            return self.next_resolved();
+        }
+        loop {
+            let line = match self.next_entry_line()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            };
+            match parse::parse_line(
+                &line,
+                &*self.source,
+                &mut self.substitution_data,
+                self.substitute,
+                self.policy,
+            ) {
                 Ok(Some(result)) => return Some(Ok(result)),
                 Ok(None) => {}
-                Err(err) => return Some(Err(err)),
+                Err(err) => return Some(Err(self.locate(err))),
             }
         }
     }
@@ -5,21 +5,53 @@
 //! file, if available, and mashes those with the actual environment variables
 //! provided by the operating system.
 
+mod command;
+#[cfg(feature = "serde")]
+mod de;
+mod document;
+mod environment;
 mod errors;
+mod example;
+mod explain;
+mod export;
 mod find;
+mod format;
 mod iter;
+mod lint;
+mod loader;
 mod parse;
+mod report;
+mod resolve;
+#[cfg(feature = "schema")]
+mod schema;
+mod write;
 
 use std::collections::HashMap;
 use std::env::{self, Vars};
 use std::ffi::OsStr;
+use std::fmt::Display;
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Once;
 
+pub use crate::command::{apply_to_command, CommandExt};
+#[cfg(feature = "serde")]
+pub use crate::de::{from_path_into, EnvDeserializer};
+pub use crate::document::{Document, DocumentEntry, DocumentLine};
+pub use crate::environment::{EnvSink, EnvSource, ProcessEnv, SubstitutionPolicy};
 pub use crate::errors::*;
-use crate::find::Finder;
-use crate::iter::Iter;
+pub use crate::example::{check_against_example, ExampleReport};
+pub use crate::explain::{explain, Definition, Explanation, ReferenceStep, ValueSource};
+pub use crate::export::{export_string, export_to_writer, export_vars, ExportFormat};
+pub use crate::format::Format;
+pub use crate::iter::{Entry, Iter, ParseDiagnostic};
+pub use crate::lint::{lint_files, lint_str, Diagnostic, LintKind, Severity};
+pub use crate::loader::DotenvLoader;
+pub use crate::report::{KeyReport, LoadOutcome, LoadReport};
+#[cfg(feature = "schema")]
+pub use crate::schema::{KeySchema, Schema, ValueType, Violation, ViolationKind};
+pub use crate::write::{to_string, to_writer};
 
 static START: Once = Once::new();
 
@@ -44,12 +76,133 @@ pub fn var<K: AsRef<OsStr>>(key: K) -> Result<String> {
     env::var(key).map_err(Error::EnvVar)
 }
 
+/// Like `var`, but reads the variable from `source`, without loading anything first.
+///
+/// Fails with `Error::EnvVar(VarError::NotPresent)` when `source` does not have the variable.
+///
+/// Examples:
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut source = HashMap::new();
+/// source.insert("FOO".to_string(), "bar".to_string());
+/// assert_eq!(dotenv_rs::var_from(&source, "FOO").unwrap(), "bar");
+/// assert!(dotenv_rs::var_from(&source, "BAZ").is_err());
+/// ```
+pub fn var_from<S: EnvSource + ?Sized>(source: &S, key: &str) -> Result<String> {
+    source
+        .get(key)
+        .ok_or(Error::EnvVar(env::VarError::NotPresent))
+}
+
+/// Like `var`, but parses the value into `T`.
+///
+/// Examples:
+///
+/// ```no_run
+///
+/// use dotenv_rs;
+///
+/// let port: u16 = dotenv_rs::var_parse("PORT").unwrap();
+/// ```
+pub fn var_parse<T, K>(key: K) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+    K: AsRef<OsStr>,
+{
+    let value = var(&key)?;
+    parse_var(&key, &value, |value| value.trim().parse())
+}
+
+/// Like `var_parse`, but returns `default` when the variable is not present.
+///
+/// Examples:
+///
+/// ```no_run
+///
+/// use dotenv_rs;
+///
+/// let workers: usize = dotenv_rs::var_or("WORKERS", 4).unwrap();
+/// ```
+pub fn var_or<T, K>(key: K, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+    K: AsRef<OsStr>,
+{
+    match var_parse(key) {
+        Err(Error::EnvVar(env::VarError::NotPresent)) => Ok(default),
+        result => result,
+    }
+}
+
+/// Like `var`, but reads the value as a boolean: `true`, `yes`, `on` and `1` are true, and
+/// `false`, `no`, `off` and `0` are false, ignoring case.
+///
+/// Examples:
+///
+/// ```no_run
+///
+/// use dotenv_rs;
+///
+/// let debug = dotenv_rs::var_bool("DEBUG").unwrap_or(false);
+/// ```
+pub fn var_bool<K: AsRef<OsStr>>(key: K) -> Result<bool> {
+    let value = var(&key)?;
+    parse_var(&key, &value, parse_bool)
+}
+
+/// Reads `value` as a boolean, the way `var_bool` does.
+pub(crate) fn parse_bool(value: &str) -> std::result::Result<bool, &'static str> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err("expected one of true/false, yes/no, on/off or 1/0"),
+    }
+}
+
+/// Like `var`, but splits the value on `separator`, trimming the items and skipping empty
+/// ones.
+///
+/// Examples:
+///
+/// ```no_run
+///
+/// use dotenv_rs;
+///
+/// let hosts = dotenv_rs::var_list("HOSTS", ",").unwrap();
+/// ```
+pub fn var_list<K: AsRef<OsStr>>(key: K, separator: &str) -> Result<Vec<String>> {
+    let value = var(&key)?;
+    Ok(value
+        .split(separator)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn parse_var<T, E, K, F>(key: K, value: &str, parse: F) -> Result<T>
+where
+    E: Display,
+    K: AsRef<OsStr>,
+    F: FnOnce(&str) -> std::result::Result<T, E>,
+{
+    parse(value).map_err(|err| Error::InvalidValue {
+        key: key.as_ref().to_string_lossy().into_owned(),
+        message: err.to_string(),
+    })
+}
+
 /// After loading the dotenv file, returns an iterator of (variable, value) pairs of strings,
 /// for all the environment variables of the current process.
 ///
 /// The returned iterator contains a snapshot of the process's environment variables at the
 /// time of this invocation, modifications to environment variables afterwards will not be
-/// reflected in the returned iterator.
+/// reflected in the returned iterator. `EnvSource` cannot list variables, so this reads the
+/// process environment directly; use `var_from` to read a variable from another source.
 ///
 /// Examples:
 ///
@@ -80,11 +233,14 @@ pub fn vars() -> Vars {
 /// dotenv_rs::from_path(my_path.as_path());
 /// ```
 pub fn from_path<P: AsRef<Path>>(path: P) -> Result<()> {
-    let iter = Iter::new(File::open(path).map_err(Error::Io)?);
-    iter.load("")
+    DotenvLoader::new().path(path).load().map(|_| ())
 }
 /// Loads the file at the specified absolute path.
 /// Set the env vars with target prefix
+///
+/// The variables keep their prefix. To load `APP_DB_URL` as `DB_URL`, use the loader:
+/// `DotenvLoader::new().path(path).prefix("APP_").strip_prefix(true).load()`.
+///
 /// Examples
 ///
 /// ```
@@ -96,8 +252,53 @@ pub fn from_path<P: AsRef<Path>>(path: P) -> Result<()> {
 /// dotenv_rs::from_path_with_prefix(my_path.as_path(), &String::from("Test"));
 /// ```
 pub fn from_path_with_prefix<P: AsRef<Path>>(path: P, prefix: &str) -> Result<()> {
-    let iter = Iter::new(File::open(path).map_err(Error::Io)?);
-    iter.load(prefix)
+    DotenvLoader::new()
+        .path(path)
+        .prefix(prefix)
+        .load()
+        .map(|_| ())
+}
+
+/// Like `from_path`, but reads the file in the given format. Nested objects of JSON, YAML and
+/// TOML files are flattened into `PARENT__CHILD` variables.
+///
+/// Examples, with the `json` feature enabled
+///
+/// ```no_run
+/// # #[cfg(feature = "json")]
+/// # {
+/// use dotenv_rs::Format;
+///
+/// dotenv_rs::from_path_format("config.json", Format::Json).unwrap();
+/// # }
+/// ```
+pub fn from_path_format<P: AsRef<Path>>(path: P, format: Format) -> Result<()> {
+    DotenvLoader::new()
+        .path(path)
+        .format(format)
+        .load()
+        .map(|_| ())
+}
+
+/// Like `from_path`, but values from the file replace variables already present in the
+/// environment.
+///
+/// Examples
+///
+/// ```
+/// use dotenv_rs;
+/// use std::env;
+/// use std::path::{Path};
+///
+/// let my_path = env::home_dir().and_then(|a| Some(a.join("/.env"))).unwrap();
+/// dotenv_rs::from_path_override(my_path.as_path());
+/// ```
+pub fn from_path_override<P: AsRef<Path>>(path: P) -> Result<()> {
+    DotenvLoader::new()
+        .path(path)
+        .override_existing(true)
+        .load()
+        .map(|_| ())
 }
 
 /// Like `from_path`, but returns an iterator over variables instead of loading into environment.
@@ -118,7 +319,7 @@ pub fn from_path_with_prefix<P: AsRef<Path>>(path: P, prefix: &str) -> Result<()
 /// }
 /// ```
 pub fn from_path_iter<P: AsRef<Path>>(path: P) -> Result<Iter<File>> {
-    Ok(Iter::new(File::open(path).map_err(Error::Io)?))
+    DotenvLoader::new().path(path).iter()
 }
 
 /// Loads the specified file from the environment's current directory or its parents in sequence.
@@ -137,12 +338,10 @@ pub fn from_path_iter<P: AsRef<Path>>(path: P) -> Result<Iter<File>> {
 /// dotenv_rs::from_filename(".env").ok();
 /// ```
 pub fn from_filename<P: AsRef<Path>>(filename: P) -> Result<PathBuf> {
-    let (path, iter) = Finder::new().filename(filename.as_ref()).find()?;
-    iter.load("")?;
-    Ok(path)
+    load_single(DotenvLoader::new().filename(filename))
 }
 /// Loads the specified file from the environment's current directory or its parents in sequence.
-/// Set the env vars with target prefix
+/// Set the env vars with target prefix, which they keep (see `from_path_with_prefix`)
 /// 
 /// # Examples
 /// ```
@@ -158,9 +357,23 @@ pub fn from_filename<P: AsRef<Path>>(filename: P) -> Result<PathBuf> {
 /// dotenv_rs::from_filename_with_prefix(".env", &String::from("Test")).ok();
 /// ```
 pub fn from_filename_with_prefix<P: AsRef<Path>>(filename: P, prefix: &str) -> Result<PathBuf> {
-    let (path, iter) = Finder::new().filename(filename.as_ref()).find()?;
-    iter.load(prefix)?;
-    Ok(path)
+    load_single(DotenvLoader::new().filename(filename).prefix(prefix))
+}
+
+/// Like `from_filename`, but values from the file replace variables already present in the
+/// environment.
+///
+/// # Examples
+/// ```
+/// use dotenv_rs;
+/// dotenv_rs::from_filename_override("custom.env").ok();
+/// ```
+pub fn from_filename_override<P: AsRef<Path>>(filename: P) -> Result<PathBuf> {
+    load_single(
+        DotenvLoader::new()
+            .filename(filename)
+            .override_existing(true),
+    )
 }
 
 /// Like `from_filename`, but returns an iterator over variables instead of loading into environment.
@@ -184,8 +397,7 @@ pub fn from_filename_with_prefix<P: AsRef<Path>>(filename: P, prefix: &str) -> R
 /// }
 /// ```
 pub fn from_filename_iter<P: AsRef<Path>>(filename: P) -> Result<Iter<File>> {
-    let (_, iter) = Finder::new().filename(filename.as_ref()).find()?;
-    Ok(iter)
+    DotenvLoader::new().filename(filename).iter()
 }
 
 /// This is usually what you want.
@@ -197,13 +409,11 @@ pub fn from_filename_iter<P: AsRef<Path>>(filename: P) -> Result<Iter<File>> {
 /// dotenv_rs::dotenv().ok();
 /// ```
 pub fn dotenv() -> Result<PathBuf> {
-    let (path, iter) = Finder::new().find()?;
-    iter.load("")?;
-    Ok(path)
+    load_single(DotenvLoader::new())
 }
 
 /// It loads the .env file located in the environment's current directory or its parents in sequence.
-/// Set the env vars with target prefix
+/// Set the env vars with target prefix, which they keep (see `from_path_with_prefix`)
 /// 
 /// # Examples
 /// ```
@@ -211,9 +421,33 @@ pub fn dotenv() -> Result<PathBuf> {
 /// dotenv_rs::dotenv_with_prefix(&String::from("Test")).ok();
 /// ```
 pub fn dotenv_with_prefix(prefix: &str) -> Result<PathBuf> {
-    let (path, iter) = Finder::new().find()?;
-    iter.load(prefix)?;
-    Ok(path)
+    load_single(DotenvLoader::new().prefix(prefix))
+}
+
+/// Like `dotenv`, but values from the .env file replace variables already present in the
+/// environment.
+///
+/// # Examples
+/// ```
+/// use dotenv_rs;
+/// dotenv_rs::dotenv_override().ok();
+/// ```
+pub fn dotenv_override() -> Result<PathBuf> {
+    load_single(DotenvLoader::new().override_existing(true))
+}
+
+/// Loads the family of .env files for the given mode: `.env`, `.env.local`, `.env.{mode}`
+/// and `.env.{mode}.local`, each searched for from the current directory. Files later in the
+/// list take precedence and can refer to the variables of the earlier ones; missing files are
+/// skipped. Returns the paths of the files loaded, or an error if none was found.
+///
+/// # Examples
+/// ```no_run
+/// use dotenv_rs;
+/// let loaded = dotenv_rs::dotenv_flow("development").unwrap();
+/// ```
+pub fn dotenv_flow(mode: &str) -> Result<Vec<PathBuf>> {
+    DotenvLoader::new().flow(mode).load()
 }
 
 /// Like `dotenv`, but returns an iterator over variables instead of loading into environment.
@@ -227,13 +461,12 @@ pub fn dotenv_with_prefix(prefix: &str) -> Result<PathBuf> {
 ///   println!("{}={}", key, val);
 /// }
 /// ```
-pub fn dotenv_iter() -> Result<iter::Iter<File>> {
-    let (_, iter) = Finder::new().find()?;
-    Ok(iter)
+pub fn dotenv_iter() -> Result<Iter<File>> {
+    DotenvLoader::new().iter()
 }
 
 /// Return the file parse result, and it will not set the env vars
-/// get the vars with target prefix
+/// get the vars with target prefix, which they keep (see `DotenvLoader::strip_prefix`)
 /// # Examples
 /// ```no_run
 /// use dotenv_rs;
@@ -241,8 +474,7 @@ pub fn dotenv_iter() -> Result<iter::Iter<File>> {
 /// dotenv_rs::get_vars_with_prefix(".test", &String::from("Test"));
 /// ```
 pub fn get_vars_with_prefix<P: AsRef<Path>>(path: P, prefix: &str) -> Result<HashMap<String, Option<String>>> {
-    let iter = Iter::new(File::open(path).map_err(Error::Io)?);
-    iter.get_vars_base(prefix)
+    DotenvLoader::new().path(path).prefix(prefix).to_map()
 }
 
 /// Return the file parse result, and it will not set the env vars
@@ -253,6 +485,11 @@ pub fn get_vars_with_prefix<P: AsRef<Path>>(path: P, prefix: &str) -> Result<Has
 /// dotenv_rs::get_vars(".test").ok();
 /// ```
 pub fn get_vars<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Option<String>>> {
-    let iter = Iter::new(File::open(path).map_err(Error::Io)?);
-    iter.get_vars()
+    DotenvLoader::new().path(path).to_map()
+}
+
+/// Loads a loader configured with a single file and returns its path.
+fn load_single(loader: DotenvLoader) -> Result<PathBuf> {
+    let mut paths = loader.load()?;
+    Ok(paths.remove(0))
 }
\ No newline at end of file
@@ -0,0 +1,408 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::document::{entry_text, logical_lines};
+use crate::environment::{EnvSource, ProcessEnv};
+use crate::errors::*;
+use crate::export::json_string;
+use crate::parse::{self, Part, Value};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// What a `Diagnostic` is about.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum LintKind {
+    /// The line cannot be parsed.
+    Parse(ParseErrorKind),
+    /// The key was already defined earlier in the file.
+    DuplicateKey,
+    /// The key only differs in case from another one of the file.
+    CaseConflict,
+    /// An unquoted value is followed by whitespace, which is dropped.
+    TrailingWhitespace,
+    /// A reference names a variable neither defined before, here or in an included file, nor
+    /// set in the environment.
+    UndefinedVariable,
+    /// The key has lowercase letters.
+    LowercaseKey,
+    /// Some entries of the file start with `export` and others do not.
+    InconsistentExport,
+}
+
+impl LintKind {
+    /// A stable name for the kind, for machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LintKind::Parse(_) => "parse-error",
+            LintKind::DuplicateKey => "duplicate-key",
+            LintKind::CaseConflict => "case-conflict",
+            LintKind::TrailingWhitespace => "trailing-whitespace",
+            LintKind::UndefinedVariable => "undefined-variable",
+            LintKind::LowercaseKey => "lowercase-key",
+            LintKind::InconsistentExport => "inconsistent-export",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintKind::Parse(_) => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+/// A problem found in a .env file by `lint_str` or `lint_files`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub path: Option<PathBuf>,
+    /// The 1-based line of the problem.
+    pub line: usize,
+    /// The 1-based column of the problem, counted in characters.
+    pub column: usize,
+    pub kind: LintKind,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+
+    /// Renders the diagnostic as a JSON object on one line, with the `path`, `line`,
+    /// `column`, `severity`, `code` and `message` fields.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"path\":{},\"line\":{},\"column\":{},\"severity\":{},\"code\":{},\"message\":{}}}",
+            self.path.as_ref().map_or(String::from("null"), |path| {
+                json_string(&path.display().to_string())
+            }),
+            self.line,
+            self.column,
+            json_string(&self.severity().to_string()),
+            json_string(self.kind.code()),
+            json_string(&self.message)
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(fmt, "{}:", path.display())?;
+        }
+        write!(
+            fmt,
+            "{}:{}: {}: {} [{}]",
+            self.line,
+            self.column,
+            self.severity(),
+            self.message,
+            self.kind.code()
+        )
+    }
+}
+
+/// Checks the text of a .env file, collecting every problem instead of stopping at the first
+/// one. References are checked against the process environment.
+///
+/// # Examples
+/// ```
+/// let diagnostics = dotenv_rs::lint_str("KEY=1\nKEY=2\n");
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].line, 2);
+/// ```
+pub fn lint_str(input: &str) -> Vec<Diagnostic> {
+    Linter::new().lint(None, input)
+}
+
+/// Checks the files at the specified paths in order. References may name variables defined
+/// in the previous files, as when the files are loaded together, or in the files they
+/// include. References are no longer checked after an include that cannot be read. Fails
+/// only if a file of `paths` cannot be read.
+pub fn lint_files<I, P>(paths: I) -> Result<Vec<Diagnostic>>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut linter = Linter::new();
+    let mut diagnostics = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        let input = fs::read_to_string(path).map_err(Error::Io)?;
+        diagnostics.extend(linter.lint(Some(path), &input));
+    }
+    Ok(diagnostics)
+}
+
+struct Linter {
+    /// The variables defined by the files checked so far.
+    defined: HashSet<String>,
+    /// Whether an included file could not be read, leaving the variables defined unknown.
+    unknown_include: bool,
+}
+
+impl Linter {
+    fn new() -> Self {
+        Linter {
+            defined: HashSet::new(),
+            unknown_include: false,
+        }
+    }
+
+    fn lint(&mut self, path: Option<&Path>, input: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut push = |line: usize, (line_offset, column): (usize, usize), kind, message| {
+            diagnostics.push(Diagnostic {
+                path: path.map(Path::to_path_buf),
+                line: line + line_offset,
+                column,
+                kind,
+                message,
+            })
+        };
+
+        // the line each key is first defined on
+        let mut keys: HashMap<String, usize> = HashMap::new();
+        let mut spellings: HashMap<String, String> = HashMap::new();
+        let mut first_export: Option<bool> = None;
+
+        for (line, raw) in logical_lines(input) {
+            if let Some(target) = parse::include_directive(&raw) {
+                let mut chain: Vec<PathBuf> = path
+                    .and_then(|path| fs::canonicalize(path).ok())
+                    .into_iter()
+                    .collect();
+                self.include(path, target, &mut chain);
+                continue;
+            }
+            let text = entry_text(&raw);
+            let entry = match parse::parse_entry(&text) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => continue,
+                Err(Error::Parse(err)) => {
+                    push(
+                        line,
+                        (err.line - 1, err.column),
+                        LintKind::Parse(err.kind),
+                        err.kind.to_string(),
+                    );
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            let key = &entry.key;
+            let key_position = position(&text, entry.key_span.start);
+            let exported = text[..entry.key_span.start].trim() == "export";
+
+            match keys.get(key) {
+                Some(first) => push(
+                    line,
+                    key_position,
+                    LintKind::DuplicateKey,
+                    format!("{} is already defined on line {}", key, first),
+                ),
+                None => {
+                    keys.insert(key.clone(), line);
+                }
+            }
+            match spellings.get(&key.to_uppercase()) {
+                Some(other) if other != key => push(
+                    line,
+                    key_position,
+                    LintKind::CaseConflict,
+                    format!("{} only differs in case from {}", key, other),
+                ),
+                Some(_) => {}
+                None => {
+                    spellings.insert(key.to_uppercase(), key.clone());
+                }
+            }
+            if key.chars().any(|c| c.is_lowercase()) {
+                push(
+                    line,
+                    key_position,
+                    LintKind::LowercaseKey,
+                    format!("{} should be uppercase", key),
+                );
+            }
+            match first_export {
+                Some(first) if first != exported => push(
+                    line,
+                    position(&text, 0),
+                    LintKind::InconsistentExport,
+                    if exported {
+                        format!("{} uses `export`, unlike the entries before it", key)
+                    } else {
+                        format!("{} lacks `export`, unlike the entries before it", key)
+                    },
+                ),
+                Some(_) => {}
+                None => first_export = Some(exported),
+            }
+
+            let written = &text[entry.value_span.clone()];
+            let after = &text[entry.value_span.end..];
+            if !written.is_empty()
+                && !written.starts_with(['\'', '"'])
+                && !after.is_empty()
+                && after.trim().is_empty()
+            {
+                push(
+                    line,
+                    position(&text, entry.value_span.end),
+                    LintKind::TrailingWhitespace,
+                    format!(
+                        "the whitespace after the value of {} is ignored, quote it to keep it",
+                        key
+                    ),
+                );
+            }
+
+            let mut undefined = Vec::new();
+            self.undefined_references(&entry.value, &mut undefined);
+            for name in undefined {
+                push(
+                    line,
+                    position(&text, entry.value_span.start),
+                    LintKind::UndefinedVariable,
+                    format!("${} is not defined before {}", name, key),
+                );
+            }
+            self.defined.insert(key.clone());
+        }
+        diagnostics
+    }
+
+    /// Adds the variables of the file named by an include directive of the file at `path`,
+    /// and of the files it includes in turn, to the variables defined. The included files are
+    /// found as `Iter` finds them; `chain` holds the files including them, to stop at a cycle.
+    fn include(&mut self, path: Option<&Path>, target: &str, chain: &mut Vec<PathBuf>) {
+        let included = match path.and_then(Path::parent) {
+            Some(directory) => directory.join(target),
+            None => PathBuf::from(target),
+        };
+        let read = fs::canonicalize(&included)
+            .and_then(|canonical| Ok((fs::read_to_string(&canonical)?, canonical)));
+        let (input, canonical) = match read {
+            Ok(read) => read,
+            Err(_) => {
+                self.unknown_include = true;
+                return;
+            }
+        };
+        if chain.contains(&canonical) {
+            return;
+        }
+        chain.push(canonical);
+        for (_, raw) in logical_lines(&input) {
+            if let Some(target) = parse::include_directive(&raw) {
+                self.include(Some(&included), target, chain);
+            } else if let Ok(Some(entry)) = parse::parse_entry(&entry_text(&raw)) {
+                self.defined.insert(entry.key);
+            }
+        }
+        chain.pop();
+    }
+
+    /// Collects the names referenced in `value` that would expand to nothing.
+    fn undefined_references(&self, value: &Value, undefined: &mut Vec<String>) {
+        for part in value {
+            if let Part::Reference(reference) = part {
+                match &reference.expansion {
+                    // an operator handles the variable being unset
+                    Some(expansion) => self.undefined_references(&expansion.word, undefined),
+                    None if !self.unknown_include
+                        && !self.defined.contains(&reference.name)
+                        && ProcessEnv.get(&reference.name).is_none() =>
+                    {
+                        undefined.push(reference.name.clone())
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+/// Returns the line, relative to the start of `text`, and the 1-based column of the byte
+/// `offset`.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let line_start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+    (
+        text[..offset].matches('\n').count(),
+        text[line_start..offset].chars().count() + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<(usize, usize, LintKind)> {
+        lint_str(input)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.column, diagnostic.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_collects_every_problem() {
+        let input = "A=1
+B=a b
+A=3
+a=4
+D=value  
+E=\"${LINT_UNDEFINED}/x ${LINT_UNDEFINED:-ok} ${A}\"
+export F=1
+G=\"unterminated
+";
+        assert_eq!(
+            kinds(input),
+            vec![
+                (2, 5, LintKind::Parse(ParseErrorKind::UnexpectedCharacter)),
+                (3, 1, LintKind::DuplicateKey),
+                (4, 1, LintKind::CaseConflict),
+                (4, 1, LintKind::LowercaseKey),
+                (5, 8, LintKind::TrailingWhitespace),
+                (6, 3, LintKind::UndefinedVariable),
+                (7, 1, LintKind::InconsistentExport),
+                (8, 3, LintKind::Parse(ParseErrorKind::UnterminatedQuote)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut diagnostic = lint_str("A=1\nA=\"2\"\n").remove(0);
+        assert_eq!(
+            diagnostic.to_json(),
+            "{\"path\":null,\"line\":2,\"column\":1,\"severity\":\"warning\",\
+             \"code\":\"duplicate-key\",\"message\":\"A is already defined on line 1\"}"
+        );
+        diagnostic.path = Some(PathBuf::from("dir/\".env\""));
+        assert!(diagnostic
+            .to_json()
+            .starts_with("{\"path\":\"dir/\\\".env\\\"\","));
+    }
+
+    #[test]
+    fn test_lint_clean_file() {
+        assert!(lint_str("# comment\nexport A='x  '\nexport B=${A} # the same\n").is_empty());
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::environment::{EnvSink, EnvSource, ProcessEnv, SubstitutionPolicy};
+use crate::errors::*;
+use crate::example::example_keys;
+use crate::find::Finder;
+use crate::format::Format;
+use crate::iter::Iter;
+use crate::report::{KeyReport, LoadOutcome, LoadReport};
+
+enum Source {
+    /// A file name searched for in the start directory and its parents.
+    Filename(PathBuf),
+    /// A path opened as is.
+    Path(PathBuf),
+    /// A file name searched for like `Filename`, but skipped when missing.
+    Optional(PathBuf),
+}
+
+/// A configurable loader for one or more .env files.
+///
+/// When several files are given, they are read in order and the later ones take precedence
+/// over the earlier ones; variables defined in a file can be substituted in the next ones.
+/// Without any file, `.env` is looked up from the current directory.
+///
+/// # Examples
+/// ```no_run
+/// use dotenv_rs::DotenvLoader;
+///
+/// DotenvLoader::new()
+///     .filename(".env")
+///     .filename(".env.local")
+///     .prefix("APP_")
+///     .override_existing(true)
+///     .load()
+///     .unwrap();
+/// ```
+pub struct DotenvLoader {
+    sources: Vec<Source>,
+    directory: Option<PathBuf>,
+    prefix: String,
+    strip_prefix: bool,
+    add_prefix: String,
+    override_existing: bool,
+    substitute: bool,
+    forward_references: bool,
+    policy: SubstitutionPolicy,
+    source: Option<Arc<dyn EnvSource + Send + Sync>>,
+    strict: bool,
+    example: Option<PathBuf>,
+    format: Format,
+}
+
+impl DotenvLoader {
+    pub fn new() -> Self {
+        DotenvLoader {
+            sources: Vec::new(),
+            directory: None,
+            prefix: String::new(),
+            strip_prefix: false,
+            add_prefix: String::new(),
+            override_existing: false,
+            substitute: true,
+            forward_references: false,
+            policy: SubstitutionPolicy::EnvFirst,
+            source: None,
+            strict: true,
+            example: None,
+            format: Format::Dotenv,
+        }
+    }
+
+    /// Adds a file to look for in the start directory or its parents in sequence.
+    pub fn filename<P: AsRef<Path>>(mut self, filename: P) -> Self {
+        self.sources
+            .push(Source::Filename(filename.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Adds the family of files used for the given mode, such as `development` or `test`:
+    /// `.env`, `.env.local`, `.env.{mode}` and `.env.{mode}.local`, from the lowest to the
+    /// highest precedence. Files of the family that do not exist are skipped, but at least
+    /// one of them must be found.
+    pub fn flow(mut self, mode: &str) -> Self {
+        let mut filenames = vec![".env".to_owned(), ".env.local".to_owned()];
+        if !mode.is_empty() {
+            filenames.push(format!(".env.{}", mode));
+            filenames.push(format!(".env.{}.local", mode));
+        }
+        for filename in filenames {
+            self.sources.push(Source::Optional(PathBuf::from(filename)));
+        }
+        self
+    }
+
+    /// Adds a file at the specified path, which is not searched for.
+    pub fn path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.sources.push(Source::Path(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Sets the directory the search for files added with `filename` starts from.
+    /// Defaults to the current directory.
+    pub fn search_from<P: AsRef<Path>>(mut self, directory: P) -> Self {
+        self.directory = Some(directory.as_ref().to_path_buf());
+        self
+    }
+
+    /// Only keeps the variables whose name starts with `prefix`.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_owned();
+        self
+    }
+
+    /// Removes the prefix given to `prefix` from the names of the variables, so that
+    /// `APP_DB_URL` is exported as `DB_URL`.
+    pub fn strip_prefix(mut self, strip_prefix: bool) -> Self {
+        self.strip_prefix = strip_prefix;
+        self
+    }
+
+    /// Prepends `prefix` to the names of the variables, after any filtering and stripping.
+    pub fn add_prefix(mut self, prefix: &str) -> Self {
+        self.add_prefix = prefix.to_owned();
+        self
+    }
+
+    /// Lets values from the files replace variables already present in the environment. A
+    /// reference to an overridden variable then expands to its new value.
+    pub fn override_existing(mut self, override_existing: bool) -> Self {
+        self.override_existing = override_existing;
+        self
+    }
+
+    /// Sets the format of the files, the `.env` syntax by default. The prefix and override
+    /// options apply to all formats alike.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Turns the expansion of `$VAR` references on (the default) or off.
+    pub fn substitution(mut self, substitute: bool) -> Self {
+        self.substitute = substitute;
+        self
+    }
+
+    /// Lets references name variables defined further down, or in a later file (off by
+    /// default). All the entries are then read before any is expanded, and variables that
+    /// refer to each other in a loop fail with `Error::ReferenceCycle`.
+    pub fn forward_references(mut self, forward_references: bool) -> Self {
+        self.forward_references = forward_references;
+        self
+    }
+
+    /// Sets where references are looked up, by default in the environment and then in the
+    /// variables of the files, or the other way around when overriding.
+    /// `SubstitutionPolicy::FileOnly` never reads the environment
+    /// while parsing; it is still read when loading without overriding, to keep the variables
+    /// already set.
+    pub fn substitution_policy(mut self, policy: SubstitutionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the environment references are looked up in, see `substitution_policy`. Defaults
+    /// to the process environment.
+    pub fn source<S: EnvSource + Send + Sync + 'static>(mut self, source: S) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    /// When strict (the default), a missing file or an invalid line is an error. Otherwise
+    /// missing files are skipped, and so are invalid lines when loading or collecting.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Makes loading fail, before setting anything, when a variable listed in the example
+    /// file at the specified path (such as `.env.example`) is neither in the files nor already
+    /// set. The names are compared after any prefix handling.
+    pub fn require_example<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.example = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Resolves the paths of the files to read, in order.
+    pub fn find(&self) -> Result<Vec<PathBuf>> {
+        let default_source = [Source::Filename(PathBuf::from(".env"))];
+        let sources = if self.sources.is_empty() {
+            &default_source[..]
+        } else {
+            &self.sources[..]
+        };
+
+        let mut paths = Vec::new();
+        let mut optional_missing = false;
+        for source in sources {
+            let path = match source {
+                Source::Filename(filename) | Source::Optional(filename) => {
+                    let finder = Finder::new().filename(filename);
+                    match &self.directory {
+                        Some(directory) => finder.directory(directory).find(),
+                        None => finder.find(),
+                    }
+                }
+                Source::Path(path) if self.strict || path.is_file() => Ok(path.clone()),
+                Source::Path(_) => continue,
+            };
+            match path {
+                Ok(path) => paths.push(path),
+                Err(ref err) if matches!(source, Source::Optional(_)) && err.not_found() => {
+                    optional_missing = true;
+                }
+                Err(ref err) if !self.strict && err.not_found() => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if paths.is_empty() && optional_missing && self.strict {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "path not found",
+            )));
+        }
+        Ok(paths)
+    }
+
+    fn open(&self) -> Result<(Vec<PathBuf>, Iter<File>)> {
+        let paths = self.find()?;
+        let mut files = Vec::new();
+        for path in &paths {
+            files.push((path.clone(), File::open(path).map_err(Error::Io)?));
+        }
+        let mut iter = Iter::from_files(files)
+            .substitute(self.substitute)
+            .policy(self.policy())
+            .resolve(self.forward_references)
+            .format(self.format);
+        if let Some(source) = &self.source {
+            iter = iter.source(Arc::clone(source));
+        }
+        Ok((paths, iter))
+    }
+
+    /// Returns an iterator over the variables of all the files, without loading them into
+    /// the environment. The variables are filtered and renamed by the prefix options; the
+    /// override policy does not apply, nothing being set.
+    pub fn iter(&self) -> Result<Iter<File>> {
+        let (_, iter) = self.open()?;
+        if self.prefix.is_empty() && self.add_prefix.is_empty() {
+            return Ok(iter);
+        }
+        let (prefix, strip_prefix, add_prefix) = (
+            self.prefix.clone(),
+            self.strip_prefix,
+            self.add_prefix.clone(),
+        );
+        Ok(iter.rename(move |key| exported_name(&prefix, strip_prefix, &add_prefix, key)))
+    }
+
+    /// Loads the variables into the environment and returns the paths of the files read.
+    pub fn load(&self) -> Result<Vec<PathBuf>> {
+        self.load_into(&mut ProcessEnv)
+    }
+
+    /// Like `load`, but sets the variables in `sink` rather than in the process environment.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::collections::HashMap;
+    /// use dotenv_rs::DotenvLoader;
+    ///
+    /// let mut vars = HashMap::new();
+    /// DotenvLoader::new().load_into(&mut vars).unwrap();
+    /// ```
+    pub fn load_into<S: EnvSink + ?Sized>(&self, sink: &mut S) -> Result<Vec<PathBuf>> {
+        self.load_into_with_report(sink).map(|report| report.paths)
+    }
+
+    /// Like `load`, but reports what happened to each variable of the files.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use dotenv_rs::{DotenvLoader, LoadOutcome};
+    ///
+    /// let report = DotenvLoader::new().load_with_report().unwrap();
+    /// for key in report.with_outcome(LoadOutcome::SkippedExisting) {
+    ///     println!("{} was already set", key.key);
+    /// }
+    /// ```
+    pub fn load_with_report(&self) -> Result<LoadReport> {
+        self.load_into_with_report(&mut ProcessEnv)
+    }
+
+    /// Like `load_into`, but reports what happened to each variable of the files.
+    pub fn load_into_with_report<S: EnvSink + ?Sized>(&self, sink: &mut S) -> Result<LoadReport> {
+        let (paths, mut iter) = self.open()?;
+        let mut keys = Vec::new();
+        while let Some(item) = iter.next() {
+            let (key, value) = match item {
+                Ok(entry) => entry,
+                Err(_) if !self.strict => continue,
+                Err(err) => return Err(err),
+            };
+            let (key, outcome) = match self.exported_name(&key) {
+                // the actual outcome is decided once the requirements are checked
+                Some(key) => (key, LoadOutcome::Set),
+                None => (key, LoadOutcome::Filtered),
+            };
+            keys.push(KeyReport {
+                key,
+                value,
+                outcome,
+                path: iter.path().map(Path::to_path_buf),
+                line: iter.line_number(),
+            });
+        }
+
+        if let Some(example) = &self.example {
+            let mut missing: Vec<String> = example_keys(example)?
+                .into_iter()
+                .filter(|key| {
+                    !keys
+                        .iter()
+                        .any(|loaded| loaded.outcome != LoadOutcome::Filtered && &loaded.key == key)
+                        && sink.get(key).is_none()
+                })
+                .collect();
+            if !missing.is_empty() {
+                missing.sort();
+                return Err(Error::MissingRequired { keys: missing });
+            }
+        }
+
+        // the file each variable was set from: unless overriding, a variable set by an earlier
+        // file may be replaced by a later one, but not by a later line of the same file.
+        let mut loaded: HashMap<String, Option<PathBuf>> = HashMap::new();
+        for report in &mut keys {
+            if report.outcome == LoadOutcome::Filtered {
+                continue;
+            }
+            let existing = sink.get(&report.key).is_some();
+            let replaceable = self.override_existing
+                || match loaded.get(&report.key) {
+                    Some(loaded_from) => loaded_from != &report.path,
+                    None => !existing,
+                };
+            report.outcome = match (replaceable, existing) {
+                (true, false) => LoadOutcome::Set,
+                (true, true) => LoadOutcome::Overridden,
+                (false, _) => LoadOutcome::SkippedExisting,
+            };
+            if replaceable {
+                sink.set(&report.key, &report.value);
+                loaded.insert(report.key.clone(), report.path.clone());
+            }
+        }
+
+        Ok(LoadReport { paths, keys })
+    }
+
+    /// Returns the variables of all the files, without loading them into the environment.
+    pub fn to_map(&self) -> Result<HashMap<String, Option<String>>> {
+        let (_, iter) = self.open()?;
+        let mut result = HashMap::new();
+        for item in iter {
+            let (key, value) = match item {
+                Ok(entry) => entry,
+                Err(_) if !self.strict => continue,
+                Err(err) => return Err(err),
+            };
+            if let Some(key) = self.exported_name(&key) {
+                result.insert(key, Some(value));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns where references are looked up before the variables of the files.
+    pub(crate) fn env_source(&self) -> Arc<dyn EnvSource + Send + Sync> {
+        match &self.source {
+            Some(source) => Arc::clone(source),
+            None => Arc::new(ProcessEnv),
+        }
+    }
+
+    /// Tells whether references are expanded, which they never are outside the `.env` syntax.
+    pub(crate) fn substitutes(&self) -> bool {
+        self.substitute && self.format == Format::Dotenv
+    }
+
+    /// Returns where references are looked up. When overriding, the variables of the files
+    /// replace those of the environment, so a reference reads them first.
+    pub(crate) fn policy(&self) -> SubstitutionPolicy {
+        match self.policy {
+            SubstitutionPolicy::EnvFirst if self.override_existing => SubstitutionPolicy::FileFirst,
+            policy => policy,
+        }
+    }
+
+    /// Tells whether references may name variables defined after them.
+    pub(crate) fn resolves_forward(&self) -> bool {
+        self.substitutes() && self.forward_references
+    }
+
+    /// Applies the prefix options to the name of a variable, returning `None` when it is
+    /// filtered out.
+    pub(crate) fn exported_name(&self, key: &str) -> Option<String> {
+        exported_name(&self.prefix, self.strip_prefix, &self.add_prefix, key)
+    }
+}
+
+fn exported_name(prefix: &str, strip_prefix: bool, add_prefix: &str, key: &str) -> Option<String> {
+    if !key.starts_with(prefix) {
+        return None;
+    }
+    let key = if strip_prefix {
+        &key[prefix.len()..]
+    } else {
+        key
+    };
+    if key.is_empty() {
+        return None;
+    }
+    Some(format!("{}{}", add_prefix, key))
+}
+
+impl Default for DotenvLoader {
+    fn default() -> Self {
+        DotenvLoader::new()
+    }
+}
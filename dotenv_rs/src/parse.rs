@@ -1,5 +1,7 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
+use crate::environment::{EnvSource, SubstitutionPolicy};
 use crate::errors::*;
 
 // for readability's sake
@@ -7,51 +9,164 @@ pub type ParsedLine = Result<Option<(String, String)>>;
 
 pub fn parse_line(
     line: &str,
+    env: &dyn EnvSource,
     substitution_data: &mut HashMap<String, Option<String>>,
+    substitute: bool,
+    policy: SubstitutionPolicy,
 ) -> ParsedLine {
-    let mut parser = LineParser::new(line, substitution_data);
-    parser.parse_line()
+    let entry = match parse_entry(line)? {
+        Some(entry) => entry,
+        None => return Ok(None),
+    };
+
+    if entry.value_span.is_empty() {
+        substitution_data.insert(entry.key.clone(), None);
+        return Ok(Some((entry.key, String::new())));
+    }
+
+    let parsed_value = if substitute {
+        expand(&entry.value, env, substitution_data, policy)?
+    } else {
+        verbatim(&entry.value)
+    };
+    substitution_data.insert(entry.key.clone(), Some(parsed_value.clone()));
+
+    Ok(Some((entry.key, parsed_value)))
+}
+
+/// An entry as written in the file, before its value is expanded.
+pub struct RawEntry {
+    pub key: String,
+    /// Where the key is in the line, in bytes.
+    pub key_span: Range<usize>,
+    /// Where the value is in the line, in bytes, leaving out the whitespace and comment
+    /// after it. Empty when the entry has no value.
+    pub value_span: Range<usize>,
+    pub value: Value,
+}
+
+/// Parses the syntax of a line, returning `None` for blank lines and comments.
+pub fn parse_entry(line: &str) -> Result<Option<RawEntry>> {
+    LineParser::new(line).parse_entry()
+}
+
+/// Tells whether `key` can be written as the name of a variable.
+pub fn is_valid_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Returns the path named by an include directive, `# @include PATH` or `source PATH`, if
+/// `line` is one. The path may be quoted.
+pub fn include_directive(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let rest = match line.strip_prefix('#') {
+        Some(comment) => comment.trim_start().strip_prefix("@include")?,
+        None => line.strip_prefix("source")?,
+    };
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let path = rest.trim();
+    let unquoted = ['\'', '"']
+        .iter()
+        .find_map(|&quote| path.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(path);
+    // `source = value` defines a variable
+    if unquoted.is_empty() || path.starts_with('=') {
+        return None;
+    }
+    Some(unquoted)
+}
+
+/// Tells whether `buffer` ends inside a quoted value, in which case the next physical line
+/// belongs to the same entry.
+pub fn needs_continuation(buffer: &str) -> bool {
+    #[derive(Clone, Copy, Eq, PartialEq)]
+    enum State {
+        Whitespace,
+        Unquoted,
+        Escape,
+        StrongQuote,
+        WeakQuote,
+        WeakQuoteEscape,
+        Comment,
+    }
+
+    let mut state = State::Whitespace;
+    for c in buffer.chars() {
+        state = match state {
+            State::Comment if c == '\n' => State::Whitespace,
+            State::Comment => State::Comment,
+            State::Escape => State::Unquoted,
+            State::StrongQuote if c == '\'' => State::Unquoted,
+            State::StrongQuote => State::StrongQuote,
+            State::WeakQuoteEscape => State::WeakQuote,
+            State::WeakQuote if c == '"' => State::Unquoted,
+            State::WeakQuote if c == '\\' => State::WeakQuoteEscape,
+            State::WeakQuote => State::WeakQuote,
+            // a `#` only starts a comment at the beginning of a line or after whitespace
+            State::Whitespace if c == '#' => State::Comment,
+            State::Whitespace | State::Unquoted => match c {
+                '\'' => State::StrongQuote,
+                '"' => State::WeakQuote,
+                '\\' => State::Escape,
+                c if c.is_whitespace() => State::Whitespace,
+                _ => State::Unquoted,
+            },
+        };
+    }
+
+    matches!(
+        state,
+        State::StrongQuote | State::WeakQuote | State::WeakQuoteEscape
+    )
 }
 
 struct LineParser<'a> {
     original_line: &'a str,
-    substitution_data: &'a mut HashMap<String, Option<String>>,
     line: &'a str,
     pos: usize,
 }
 
 impl<'a> LineParser<'a> {
-    fn new(
-        line: &'a str,
-        substitution_data: &'a mut HashMap<String, Option<String>>,
-    ) -> LineParser<'a> {
+    fn new(line: &'a str) -> LineParser<'a> {
         LineParser {
             original_line: line,
-            substitution_data,
             line: line.trim_end(), // we don’t want trailing whitespace
             pos: 0,
         }
     }
 
-    fn err(&self) -> Error {
-        Error::LineParse(self.original_line.into(), self.pos)
+    fn err(&self, kind: ParseErrorKind) -> Error {
+        self.err_at(self.pos, kind)
+    }
+
+    fn err_at(&self, offset: usize, kind: ParseErrorKind) -> Error {
+        Error::Parse(ParseError::new(self.original_line, offset, kind))
     }
 
-    fn parse_line(&mut self) -> ParsedLine {
+    fn parse_entry(&mut self) -> Result<Option<RawEntry>> {
         self.skip_whitespace();
         // if its an empty line or a comment, skip it
         if self.line.is_empty() || self.line.starts_with('#') {
             return Ok(None);
         }
 
+        let mut key_start = self.pos;
         let mut key = self.parse_key()?;
+        let mut key_end = self.pos;
         self.skip_whitespace();
 
         // export can be either an optional prefix or a key itself
         if key == "export" {
             // here we check for an optional `=`, below we throw directly when it’s not found.
             if self.expect_equal().is_err() {
+                key_start = self.pos;
                 key = self.parse_key()?;
+                key_end = self.pos;
                 self.skip_whitespace();
                 self.expect_equal()?;
             }
@@ -61,15 +176,23 @@ impl<'a> LineParser<'a> {
         self.skip_whitespace();
 
         if self.line.is_empty() || self.line.starts_with('#') {
-            self.substitution_data.insert(key.clone(), None);
-            return Ok(Some((key, String::new())));
+            return Ok(Some(RawEntry {
+                key,
+                key_span: key_start..key_end,
+                value_span: self.pos..self.pos,
+                value: Value::new(),
+            }));
         }
 
-        let parsed_value = parse_value(self.line, &mut self.substitution_data)?;
-        self.substitution_data
-            .insert(key.clone(), Some(parsed_value.clone()));
+        let (value, length) = parse_value(self.line)
+            .map_err(|(kind, offset)| self.err_at(self.pos + offset, kind))?;
 
-        Ok(Some((key, parsed_value)))
+        Ok(Some(RawEntry {
+            key,
+            key_span: key_start..key_end,
+            value_span: self.pos..self.pos + length,
+            value,
+        }))
     }
 
     fn parse_key(&mut self) -> Result<String> {
@@ -77,7 +200,7 @@ impl<'a> LineParser<'a> {
             .line
             .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
         {
-            return Err(self.err());
+            return Err(self.err(ParseErrorKind::InvalidKey));
         }
         let index = match self
             .line
@@ -94,7 +217,7 @@ impl<'a> LineParser<'a> {
 
     fn expect_equal(&mut self) -> Result<()> {
         if !self.line.starts_with('=') {
-            return Err(self.err());
+            return Err(self.err(ParseErrorKind::MissingEquals));
         }
         self.line = &self.line[1..];
         self.pos += 1;
@@ -119,22 +242,84 @@ enum SubstitutionMode {
     EscapedBlock,
 }
 
-fn parse_value(
-    input: &str,
-    substitution_data: &mut HashMap<String, Option<String>>,
-) -> Result<String> {
+/// A parsed value, kept as literal text and variable references until it is expanded.
+pub type Value = Vec<Part>;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Part {
+    Literal(String),
+    Reference(Reference),
+}
+
+/// A `$NAME` or `${NAME}` reference found in a value, with an optional expansion operator.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub expansion: Option<Expansion>,
+    /// The reference as written, used when substitution is turned off.
+    pub raw: String,
+}
+
+/// The right-hand side of a `${NAME<operator>word}` reference.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Expansion {
+    pub operator: Operator,
+    /// Set when the operator is written with a leading `:`, in which case an empty value
+    /// is treated the same as an unset one.
+    pub check_empty: bool,
+    pub word: Value,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operator {
+    /// `${NAME-word}`: use `word` when `NAME` is unset.
+    Default,
+    /// `${NAME+word}`: use `word` when `NAME` is set.
+    Alternative,
+    /// `${NAME?word}`: fail with `word` as the message when `NAME` is unset.
+    Required,
+}
+
+fn push_literal(output: &mut Value, c: char) {
+    if let Some(Part::Literal(text)) = output.last_mut() {
+        text.push(c);
+    } else {
+        output.push(Part::Literal(c.to_string()));
+    }
+}
+
+fn push_reference(output: &mut Value, name: String) {
+    output.push(Part::Reference(Reference {
+        raw: format!("${}", name),
+        name,
+        expansion: None,
+    }));
+}
+
+/// A parse failure, along with the byte offset it happened at.
+type Failure = (ParseErrorKind, usize);
+
+/// Parses a value, returning it along with its length in bytes, which leaves out the
+/// whitespace and comment after it.
+fn parse_value(input: &str) -> std::result::Result<(Value, usize), Failure> {
+    let mut length = input.len();
     let mut strong_quote = false; // '
     let mut weak_quote = false; // "
     let mut escaped = false;
     let mut expecting_end = false;
+    // where the currently open quote or substitution starts, for error reporting
+    let mut quote_start = 0;
+    let mut substitution_start = 0;
 
     //FIXME can this be done without yet another allocation per line?
-    let mut output = String::new();
+    let mut output = Value::new();
 
     let mut substitution_mode = SubstitutionMode::None;
     let mut substitution_name = String::new();
+    // nesting level of the braces inside a `${...}` block
+    let mut substitution_depth = 0;
 
-    for (index, c) in input.chars().enumerate() {
+    for (index, c) in input.char_indices() {
         //the regex _should_ already trim whitespace off the end
         //expecting_end is meant to permit: k=v #comment
         //without affecting: k=v#comment
@@ -145,7 +330,7 @@ fn parse_value(
             } else if c == '#' {
                 break;
             } else {
-                return Err(Error::LineParse(input.to_owned(), index));
+                return Err((ParseErrorKind::UnexpectedCharacter, index));
             }
         } else if escaped {
             //TODO I tried handling literal \r but various issues
@@ -153,10 +338,10 @@ fn parse_value(
             //(actually handling backslash 0x10 would be a whole other matter)
             //then there's \v \f bell hex... etc
             match c {
-                '\\' | '\'' | '"' | '$' | ' ' => output.push(c),
-                'n' => output.push('\n'), // handle \n case
+                '\\' | '\'' | '"' | '$' | ' ' => push_literal(&mut output, c),
+                'n' => push_literal(&mut output, '\n'), // handle \n case
                 _ => {
-                    return Err(Error::LineParse(input.to_owned(), index));
+                    return Err((ParseErrorKind::InvalidEscape, index));
                 }
             }
 
@@ -165,7 +350,7 @@ fn parse_value(
             if c == '\'' {
                 strong_quote = false;
             } else {
-                output.push(c);
+                push_literal(&mut output, c);
             }
         } else if substitution_mode != SubstitutionMode::None {
             if c.is_alphanumeric() {
@@ -177,12 +362,9 @@ fn parse_value(
                         if c == '{' && substitution_name.is_empty() {
                             substitution_mode = SubstitutionMode::EscapedBlock;
                         } else {
-                            apply_substitution(
-                                substitution_data,
-                                &substitution_name.drain(..).collect::<String>(),
-                                &mut output,
-                            );
+                            push_reference(&mut output, std::mem::take(&mut substitution_name));
                             if c == '$' {
+                                substitution_start = index;
                                 substitution_mode = if !strong_quote && !escaped {
                                     SubstitutionMode::Block
                                 } else {
@@ -190,25 +372,31 @@ fn parse_value(
                                 }
                             } else {
                                 substitution_mode = SubstitutionMode::None;
-                                output.push(c);
+                                push_literal(&mut output, c);
                             }
                         }
                     }
                     SubstitutionMode::EscapedBlock => {
-                        if c == '}' {
+                        if c == '}' && substitution_depth == 0 {
                             substitution_mode = SubstitutionMode::None;
-                            apply_substitution(
-                                substitution_data,
-                                &substitution_name.drain(..).collect::<String>(),
-                                &mut output,
-                            );
+                            let reference = parse_reference(&std::mem::take(
+                                &mut substitution_name,
+                            ))
+                            .ok_or((ParseErrorKind::InvalidSubstitution, substitution_start))?;
+                            output.push(Part::Reference(reference));
                         } else {
+                            if c == '{' {
+                                substitution_depth += 1;
+                            } else if c == '}' {
+                                substitution_depth -= 1;
+                            }
                             substitution_name.push(c);
                         }
                     }
                 }
             }
         } else if c == '$' {
+            substitution_start = index;
             substitution_mode = if !strong_quote && !escaped {
                 SubstitutionMode::Block
             } else {
@@ -220,58 +408,232 @@ fn parse_value(
             } else if c == '\\' {
                 escaped = true;
             } else {
-                output.push(c);
+                push_literal(&mut output, c);
             }
         } else if c == '\'' {
             strong_quote = true;
+            quote_start = index;
         } else if c == '"' {
             weak_quote = true;
+            quote_start = index;
         } else if c == '\\' {
             escaped = true;
         } else if c == ' ' || c == '\t' {
             expecting_end = true;
+            length = index;
         } else {
-            output.push(c);
+            push_literal(&mut output, c);
         }
     }
 
     //XXX also fail if escaped? or...
-    if substitution_mode == SubstitutionMode::EscapedBlock || strong_quote || weak_quote {
-        let value_length = input.len();
-        Err(Error::LineParse(
-            input.to_owned(),
-            if value_length == 0 {
-                0
-            } else {
-                value_length - 1
-            },
-        ))
+    if substitution_mode == SubstitutionMode::EscapedBlock {
+        Err((ParseErrorKind::UnterminatedSubstitution, substitution_start))
+    } else if strong_quote || weak_quote {
+        Err((ParseErrorKind::UnterminatedQuote, quote_start))
     } else {
-        apply_substitution(
-            substitution_data,
-            &substitution_name.drain(..).collect::<String>(),
-            &mut output,
-        );
-        Ok(output)
+        if substitution_mode == SubstitutionMode::Block {
+            push_reference(&mut output, substitution_name);
+        }
+        Ok((output, length))
     }
 }
 
-fn apply_substitution(
-    substitution_data: &mut HashMap<String, Option<String>>,
-    substitution_name: &str,
-    output: &mut String,
-) {
-    if let Ok(environment_value) = std::env::var(substitution_name) {
-        output.push_str(&environment_value);
-    } else {
-        let stored_value = substitution_data
-            .get(substitution_name)
-            .unwrap_or(&None)
-            .to_owned();
-        output.push_str(&stored_value.unwrap_or_else(String::new));
+/// Parses the inside of a `${...}` block: a name, optionally followed by one of the
+/// `-`, `+` or `?` operators (each possibly prefixed with `:`) and a word.
+fn parse_reference(content: &str) -> Option<Reference> {
+    let name_end = content.find([':', '-', '+', '?']).unwrap_or(content.len());
+    let (name, rest) = content.split_at(name_end);
+    let raw = format!("${{{}}}", content);
+    if rest.is_empty() {
+        return Some(Reference {
+            name: name.to_owned(),
+            expansion: None,
+            raw,
+        });
+    }
+    if name.is_empty() {
+        return None;
+    }
+
+    let (check_empty, rest) = match rest.strip_prefix(':') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let operator = match rest.chars().next()? {
+        '-' => Operator::Default,
+        '+' => Operator::Alternative,
+        '?' => Operator::Required,
+        _ => return None,
+    };
+
+    Some(Reference {
+        name: name.to_owned(),
+        expansion: Some(Expansion {
+            operator,
+            check_empty,
+            word: parse_word(&rest[1..])?,
+        }),
+        raw,
+    })
+}
+
+/// Parses the word of an expansion operator. Quotes have no special meaning here, a
+/// backslash escapes the next character and `$` starts a (possibly nested) reference.
+fn parse_word(word: &str) -> Option<Value> {
+    let mut output = Value::new();
+    let mut chars = word.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => push_literal(&mut output, chars.next()?),
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let mut content = String::new();
+                let mut depth = 0;
+                loop {
+                    match chars.next()? {
+                        '}' if depth == 0 => break,
+                        c => {
+                            if c == '{' {
+                                depth += 1;
+                            } else if c == '}' {
+                                depth -= 1;
+                            }
+                            content.push(c);
+                        }
+                    }
+                }
+                output.push(Part::Reference(parse_reference(&content)?));
+            }
+            '$' if chars.peek().is_some_and(|c| c.is_alphanumeric()) => {
+                let mut name = String::new();
+                while let Some(c) = chars.peek().filter(|c| c.is_alphanumeric()) {
+                    name.push(*c);
+                    chars.next();
+                }
+                push_reference(&mut output, name);
+            }
+            c => push_literal(&mut output, c),
+        }
+    }
+
+    Some(output)
+}
+
+/// Expands the references in `value`, looking them up in `env` and in the variables defined so
+/// far as `policy` says.
+pub fn expand(
+    value: &[Part],
+    env: &dyn EnvSource,
+    substitution_data: &HashMap<String, Option<String>>,
+    policy: SubstitutionPolicy,
+) -> Result<String> {
+    let mut output = String::new();
+    for part in value {
+        match part {
+            Part::Literal(text) => output.push_str(text),
+            Part::Reference(reference) => output.push_str(&expand_reference(
+                reference,
+                env,
+                substitution_data,
+                policy,
+            )?),
+        }
+    }
+    Ok(output)
+}
+
+/// Renders `value` without expanding its references.
+pub fn verbatim(value: &[Part]) -> String {
+    value
+        .iter()
+        .map(|part| match part {
+            Part::Literal(text) => text.as_str(),
+            Part::Reference(reference) => reference.raw.as_str(),
+        })
+        .collect()
+}
+
+/// Collects the names referenced in `value`, including in the words of their operators.
+pub fn reference_names(value: &[Part], names: &mut Vec<String>) {
+    for part in value {
+        if let Part::Reference(reference) = part {
+            names.push(reference.name.clone());
+            if let Some(expansion) = &reference.expansion {
+                reference_names(&expansion.word, names);
+            }
+        }
+    }
+}
+
+fn expand_reference(
+    reference: &Reference,
+    env: &dyn EnvSource,
+    substitution_data: &HashMap<String, Option<String>>,
+    policy: SubstitutionPolicy,
+) -> Result<String> {
+    let value = lookup(env, substitution_data, policy, &reference.name);
+    let expansion = match &reference.expansion {
+        Some(expansion) => expansion,
+        None => return Ok(value.unwrap_or_default()),
     };
+
+    let is_set = match &value {
+        Some(value) => !(expansion.check_empty && value.is_empty()),
+        None => false,
+    };
+    match (expansion.operator, is_set) {
+        (Operator::Default, true) | (Operator::Required, true) => Ok(value.unwrap_or_default()),
+        (Operator::Default, false) | (Operator::Alternative, true) => {
+            expand(&expansion.word, env, substitution_data, policy)
+        }
+        (Operator::Alternative, false) => Ok(String::new()),
+        (Operator::Required, false) => {
+            let message = expand(&expansion.word, env, substitution_data, policy)?;
+            Err(Error::UnsetVariable {
+                name: reference.name.clone(),
+                message: if message.is_empty() {
+                    String::from("parameter null or not set")
+                } else {
+                    message
+                },
+                path: None,
+                line: None,
+            })
+        }
+    }
 }
 
+fn lookup(
+    env: &dyn EnvSource,
+    substitution_data: &HashMap<String, Option<String>>,
+    policy: SubstitutionPolicy,
+    name: &str,
+) -> Option<String> {
+    let from_env = || {
+        if policy.reads_env() {
+            env.get(name)
+        } else {
+            None
+        }
+    };
+    let from_files = || {
+        if policy.reads_files() {
+            substitution_data
+                .get(name)
+                .map(|stored_value| stored_value.clone().unwrap_or_default())
+        } else {
+            None
+        }
+    };
+    match policy {
+        SubstitutionPolicy::FileFirst | SubstitutionPolicy::FileOnly => {
+            from_files().or_else(from_env)
+        }
+        _ => from_env().or_else(from_files),
+    }
+}
 #[cfg(test)]
 mod test {
     use crate::iter::Iter;
@@ -392,6 +754,53 @@ KEY7="line 1\nline 2"
         }
     }
 
+    #[test]
+    fn test_parse_multiline_values() {
+        let mut actual_iter = Iter::new(
+            r#"KEY=1
+PEM="-----BEGIN KEY-----
+abc\"def
+-----END KEY-----"
+JSON='{
+  "a": "it''s"
+}' # trailing comment
+# it's a comment with a quote
+KEY2="line" # don't continue
+KEY3=4
+"#
+            .as_bytes(),
+        );
+
+        let expected = vec![
+            ("KEY", "1", 1),
+            ("PEM", "-----BEGIN KEY-----\nabc\"def\n-----END KEY-----", 2),
+            ("JSON", "{\n  \"a\": \"its\"\n}", 5),
+            ("KEY2", "line", 9),
+            ("KEY3", "4", 10),
+        ];
+
+        for (key, value, line) in expected {
+            let (actual_key, actual_value) = actual_iter.next().unwrap().unwrap();
+            assert_eq!(key, actual_key);
+            assert_eq!(value, actual_value);
+            assert_eq!(line, actual_iter.line_number());
+        }
+        assert!(actual_iter.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_multiline_unterminated() {
+        let mut actual_iter = Iter::new("KEY=1\nKEY2=\"open\nKEY3=3\n".as_bytes());
+
+        assert_eq!(
+            ("KEY".to_string(), "1".to_string()),
+            actual_iter.next().unwrap().unwrap()
+        );
+        assert!(actual_iter.next().unwrap().is_err());
+        assert_eq!(2, actual_iter.line_number());
+        assert!(actual_iter.next().is_none());
+    }
+
     #[test]
     fn test_parse_value_escapes_invalid() {
         let actual_iter = Iter::new(
@@ -408,10 +817,39 @@ KEY4=h\8u
             assert!(actual.is_err());
         }
     }
+
+    #[test]
+    fn test_include_directive() {
+        assert_eq!(
+            include_directive("# @include ../shared.env"),
+            Some("../shared.env")
+        );
+        assert_eq!(
+            include_directive("#@include 'with space.env' "),
+            Some("with space.env")
+        );
+        assert_eq!(
+            include_directive("source ./common.env"),
+            Some("./common.env")
+        );
+        assert_eq!(
+            include_directive("  source \"common.env\""),
+            Some("common.env")
+        );
+        assert_eq!(include_directive("source = value"), None);
+        assert_eq!(include_directive("source=value"), None);
+        assert_eq!(include_directive("sources x"), None);
+        assert_eq!(include_directive("# @included x"), None);
+        assert_eq!(include_directive("# @include"), None);
+    }
 }
 
 #[cfg(test)]
 mod variable_substitution_tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use crate::environment::SubstitutionPolicy;
     use crate::iter::Iter;
 
     fn assert_parsed_string(input_string: &str, expected_parse_result: Vec<(&str, &str)>) {
@@ -537,6 +975,74 @@ mod variable_substitution_tests {
         );
     }
 
+    #[test]
+    fn default_value_operators() {
+        assert_parsed_string(
+            r#"
+    EMPTY=
+    SET=value
+    KEY1=${UNSET_DEFAULT:-fallback}
+    KEY2=${EMPTY:-fallback}
+    KEY3=${EMPTY-fallback}
+    KEY4=${SET:-fallback}
+    KEY5="${UNSET_DEFAULT-with spaces}"
+    KEY6=${UNSET_DEFAULT:-${SET}/nested}
+    KEY7=${UNSET_DEFAULT:-\$SET}
+    "#,
+            vec![
+                ("EMPTY", ""),
+                ("SET", "value"),
+                ("KEY1", "fallback"),
+                ("KEY2", "fallback"),
+                ("KEY3", ""),
+                ("KEY4", "value"),
+                ("KEY5", "with spaces"),
+                ("KEY6", "value/nested"),
+                ("KEY7", "$SET"),
+            ],
+        );
+    }
+
+    #[test]
+    fn alternative_value_operators() {
+        assert_parsed_string(
+            r#"
+    EMPTY=
+    SET=value
+    KEY1=${SET:+alt}
+    KEY2=${EMPTY:+alt}
+    KEY3=${EMPTY+alt}
+    KEY4=>${UNSET_ALTERNATIVE+alt}<
+    "#,
+            vec![
+                ("EMPTY", ""),
+                ("SET", "value"),
+                ("KEY1", "alt"),
+                ("KEY2", ""),
+                ("KEY3", "alt"),
+                ("KEY4", "><"),
+            ],
+        );
+    }
+
+    #[test]
+    fn required_value_operators() {
+        assert_parsed_string(
+            r#"
+    EMPTY=
+    SET=value
+    KEY1=${SET:?must be set}
+    KEY2=${EMPTY?}
+    "#,
+            vec![
+                ("EMPTY", ""),
+                ("SET", "value"),
+                ("KEY1", "value"),
+                ("KEY2", ""),
+            ],
+        );
+    }
+
     #[test]
     fn consequent_substitutions() {
         assert_parsed_string(
@@ -563,11 +1069,34 @@ mod variable_substitution_tests {
             vec![("KEY2", "_2"), ("KEY", "><>_2<")],
         );
     }
+
+    #[test]
+    fn substitution_policies() {
+        let input = "USER=file\nONLY_FILE=file\nKEY=${USER}-${ONLY_ENV}-${ONLY_FILE}\n";
+        let env: HashMap<String, String> = vec![("USER", "env"), ("ONLY_ENV", "env")]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect();
+        for (policy, expected) in [
+            (SubstitutionPolicy::EnvFirst, "env-env-file"),
+            (SubstitutionPolicy::FileFirst, "file-env-file"),
+            (SubstitutionPolicy::FileOnly, "file--file"),
+            (SubstitutionPolicy::EnvOnly, "env-env-"),
+        ] {
+            let vars: HashMap<String, String> = Iter::new(input.as_bytes())
+                .source(Arc::new(env.clone()))
+                .policy(policy)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(vars["KEY"], expected, "{:?}", policy);
+        }
+    }
 }
 
 #[cfg(test)]
 mod error_tests {
-    use crate::errors::Error::LineParse;
+    use crate::errors::Error::{Parse, UnsetVariable};
+    use crate::errors::ParseErrorKind;
     use crate::iter::Iter;
 
     #[test]
@@ -591,14 +1120,16 @@ mod error_tests {
         if let Ok(first_line) = &parsed_values[0] {
             assert_eq!(first_line, &(String::from("KEY"), String::from("VALUE")))
         } else {
-            assert!(false, "Expected the first value to be parsed")
+            panic!("Expected the first value to be parsed")
         }
 
-        if let Err(LineParse(second_value, index)) = &parsed_values[1] {
-            assert_eq!(second_value, wrong_value);
-            assert_eq!(*index, wrong_value.len() - 1)
+        if let Err(Parse(err)) = &parsed_values[1] {
+            assert_eq!(err.text, format!("    KEY1={}", wrong_value));
+            assert_eq!(err.line, 3);
+            assert_eq!(err.column, 11);
+            assert_eq!(err.kind, ParseErrorKind::UnterminatedSubstitution);
         } else {
-            assert!(false, "Expected the second value not to be parsed")
+            panic!("Expected the second value not to be parsed")
         }
     }
 
@@ -610,14 +1141,62 @@ mod error_tests {
 
         assert_eq!(parsed_values.len(), 1);
 
-        if let Err(LineParse(second_value, index)) = &parsed_values[0] {
-            assert_eq!(second_value, wrong_key_value);
-            assert_eq!(*index, 0)
+        if let Err(Parse(err)) = &parsed_values[0] {
+            assert_eq!(err.text, wrong_key_value);
+            assert_eq!(err.line, 1);
+            assert_eq!(err.column, 1);
+            assert_eq!(err.kind, ParseErrorKind::InvalidKey);
         } else {
-            assert!(false, "Expected the second value not to be parsed")
+            panic!("Expected the second value not to be parsed")
+        }
+    }
+
+    #[test]
+    fn should_fail_on_required_unset_variable() {
+        let parsed_values: Vec<_> = Iter::new(
+            r#"
+    EMPTY=
+    KEY1=${UNSET_REQUIRED:?must be set}
+    KEY2=${EMPTY:?}
+    KEY3=${UNSET_REQUIRED?}
+    "#
+            .as_bytes(),
+        )
+        .collect();
+
+        assert_eq!(parsed_values.len(), 4);
+        let expected = vec![
+            ("UNSET_REQUIRED", "must be set"),
+            ("EMPTY", "parameter null or not set"),
+            ("UNSET_REQUIRED", "parameter null or not set"),
+        ];
+        for (parsed_value, (expected_name, expected_message)) in
+            parsed_values[1..].iter().zip(expected)
+        {
+            if let Err(UnsetVariable {
+                name,
+                message,
+                line,
+                ..
+            }) = parsed_value
+            {
+                assert_eq!(name, expected_name);
+                assert_eq!(message, expected_message);
+                assert!(line.is_some());
+            } else {
+                panic!("Expected the value not to be parsed")
+            }
         }
     }
 
+    #[test]
+    fn should_not_parse_unknown_operator() {
+        let parsed_values: Vec<_> = Iter::new("KEY=${VALUE:=default}".as_bytes()).collect();
+
+        assert_eq!(parsed_values.len(), 1);
+        assert!(parsed_values[0].is_err());
+    }
+
     #[test]
     fn should_not_parse_illegal_format() {
         let wrong_format = r"<><><>";
@@ -625,11 +1204,12 @@ mod error_tests {
 
         assert_eq!(parsed_values.len(), 1);
 
-        if let Err(LineParse(wrong_value, index)) = &parsed_values[0] {
-            assert_eq!(wrong_value, wrong_format);
-            assert_eq!(*index, 0)
+        if let Err(Parse(err)) = &parsed_values[0] {
+            assert_eq!(err.text, wrong_format);
+            assert_eq!(err.column, 1);
+            assert_eq!(err.kind, ParseErrorKind::InvalidKey);
         } else {
-            assert!(false, "Expected the second value not to be parsed")
+            panic!("Expected the second value not to be parsed")
         }
     }
 
@@ -641,11 +1221,44 @@ mod error_tests {
 
         assert_eq!(parsed_values.len(), 1);
 
-        if let Err(LineParse(wrong_value, index)) = &parsed_values[0] {
-            assert_eq!(wrong_value, wrong_escape);
-            assert_eq!(*index, wrong_escape.find("\\").unwrap() + 1)
+        if let Err(Parse(err)) = &parsed_values[0] {
+            assert_eq!(err.text, format!("VALUE={}", wrong_escape));
+            assert_eq!(
+                err.column,
+                "VALUE=".len() + wrong_escape.find("\\").unwrap() + 2
+            );
+            assert_eq!(err.kind, ParseErrorKind::InvalidEscape);
         } else {
-            assert!(false, "Expected the second value not to be parsed")
+            panic!("Expected the second value not to be parsed")
+        }
+    }
+
+    #[test]
+    fn should_report_error_positions() {
+        let parsed_values: Vec<_> = Iter::new(
+            r#"KEY=1
+very bacon = yes
+KEY2="multi
+line" value
+KEY3='open
+"#
+            .as_bytes(),
+        )
+        .collect();
+
+        let expected = vec![
+            (2, 6, ParseErrorKind::MissingEquals),
+            (4, 7, ParseErrorKind::UnexpectedCharacter),
+            (5, 6, ParseErrorKind::UnterminatedQuote),
+        ];
+        assert_eq!(parsed_values.len(), 4);
+        for (parsed_value, (line, column, kind)) in parsed_values[1..].iter().zip(expected) {
+            if let Err(Parse(err)) = parsed_value {
+                assert_eq!((err.line, err.column, err.kind), (line, column, kind));
+                assert_eq!(err.path, None);
+            } else {
+                panic!("Expected the value not to be parsed")
+            }
         }
     }
 }
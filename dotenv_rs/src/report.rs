@@ -0,0 +1,55 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// What loading did with each variable of the files, as returned by `Iter::load` and
+/// `DotenvLoader::load_with_report`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LoadReport {
+    /// The files read, in order.
+    pub paths: Vec<PathBuf>,
+    /// The variables, in the order they were read.
+    pub keys: Vec<KeyReport>,
+}
+
+impl LoadReport {
+    /// Returns the reports of the variables that ended up with the given outcome.
+    pub fn with_outcome(&self, outcome: LoadOutcome) -> impl Iterator<Item = &KeyReport> {
+        self.keys.iter().filter(move |key| key.outcome == outcome)
+    }
+}
+
+/// A variable read while loading, and what happened to it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyReport {
+    /// The name the variable was exported as, or the name in the file when filtered out.
+    pub key: String,
+    pub value: String,
+    pub outcome: LoadOutcome,
+    pub path: Option<PathBuf>,
+    /// The 1-based line the variable is defined on.
+    pub line: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum LoadOutcome {
+    /// The variable was not set, and now is.
+    Set,
+    /// The variable was already set and kept its value.
+    SkippedExisting,
+    /// The variable does not start with the prefix loaded.
+    Filtered,
+    /// The variable was already set and its value was replaced.
+    Overridden,
+}
+
+impl fmt::Display for LoadOutcome {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            LoadOutcome::Set => "set",
+            LoadOutcome::SkippedExisting => "skipped-existing",
+            LoadOutcome::Filtered => "filtered",
+            LoadOutcome::Overridden => "overridden",
+        })
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use crate::environment::{EnvSource, SubstitutionPolicy};
+use crate::errors::*;
+use crate::parse::{self, Value};
+
+/// An entry read by `Iter` in resolve mode, before its value is expanded.
+pub(crate) struct Pending {
+    pub key: String,
+    pub value: Value,
+    /// Set when the entry has no value at all, which reads as unset rather than empty.
+    pub empty: bool,
+}
+
+enum Failure {
+    Cycle(Vec<String>),
+    Error(Error),
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum State {
+    Unvisited,
+    Visiting,
+    Done,
+}
+
+/// Expands the values of `entries`, returning one result per entry.
+///
+/// A reference names the entry defining the variable last before it, as when reading line by
+/// line, or, when there is none, the last entry defining it after it. Entries are expanded once
+/// the ones they refer to are, and the entries of a reference cycle fail with
+/// `Error::ReferenceCycle`. `policy` decides between the environment and the entries.
+pub(crate) fn resolve(
+    entries: &[Pending],
+    env: &dyn EnvSource,
+    policy: SubstitutionPolicy,
+) -> Vec<Result<String>> {
+    let mut resolver = Resolver {
+        entries,
+        env,
+        policy,
+        positions: HashMap::new(),
+        states: vec![State::Unvisited; entries.len()],
+        results: entries.iter().map(|_| None).collect(),
+        stack: Vec::new(),
+    };
+    for (index, entry) in entries.iter().enumerate() {
+        resolver
+            .positions
+            .entry(entry.key.as_str())
+            .or_default()
+            .push(index);
+    }
+    for index in 0..entries.len() {
+        resolver.visit(index);
+    }
+    resolver
+        .results
+        .into_iter()
+        .map(|result| match result.expect("every entry is visited") {
+            Ok(value) => Ok(value.unwrap_or_default()),
+            Err(Failure::Cycle(cycle)) => Err(Error::ReferenceCycle {
+                cycle,
+                path: None,
+                line: None,
+            }),
+            Err(Failure::Error(err)) => Err(err),
+        })
+        .collect()
+}
+
+struct Resolver<'a> {
+    entries: &'a [Pending],
+    env: &'a dyn EnvSource,
+    policy: SubstitutionPolicy,
+    /// The entries defining each variable, in order.
+    positions: HashMap<&'a str, Vec<usize>>,
+    states: Vec<State>,
+    results: Vec<Option<std::result::Result<Option<String>, Failure>>>,
+    /// The entries being visited, outermost first.
+    stack: Vec<usize>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Returns the entry a reference to `name` from the entry at `index` stands for.
+    fn target(&self, index: usize, name: &str) -> Option<usize> {
+        let from_env = match self.policy {
+            SubstitutionPolicy::EnvFirst => self.env.get(name).is_some(),
+            policy => !policy.reads_files(),
+        };
+        if from_env {
+            return None;
+        }
+        let positions = self.positions.get(name)?;
+        positions
+            .iter()
+            .rev()
+            .find(|&&position| position < index)
+            .or_else(|| positions.last())
+            .copied()
+    }
+
+    fn visit(&mut self, index: usize) {
+        if self.states[index] != State::Unvisited {
+            return;
+        }
+        self.states[index] = State::Visiting;
+        self.stack.push(index);
+
+        let mut names = Vec::new();
+        parse::reference_names(&self.entries[index].value, &mut names);
+        let targets: Vec<(String, Option<usize>)> = names
+            .into_iter()
+            .map(|name| {
+                let target = self.target(index, &name);
+                (name, target)
+            })
+            .collect();
+        for target in targets.iter().filter_map(|(_, target)| *target) {
+            match self.states[target] {
+                State::Unvisited => self.visit(target),
+                State::Visiting => self.fail_cycle(target),
+                State::Done => {}
+            }
+        }
+
+        if self.results[index].is_none() {
+            let entry = &self.entries[index];
+            let mut data = HashMap::new();
+            for (name, target) in &targets {
+                // the entries that failed are left unset, as when reading line by line
+                if let Some(Some(Ok(value))) = target.map(|target| &self.results[target]) {
+                    data.insert(name.clone(), value.clone());
+                }
+            }
+            self.results[index] = Some(if entry.empty {
+                Ok(None)
+            } else {
+                parse::expand(&entry.value, self.env, &data, self.policy)
+                    .map(Some)
+                    .map_err(Failure::Error)
+            });
+        }
+        self.states[index] = State::Done;
+        self.stack.pop();
+    }
+
+    /// Fails the entries of the cycle closed by a reference to `target`, which is being
+    /// visited.
+    fn fail_cycle(&mut self, target: usize) {
+        let start = self
+            .stack
+            .iter()
+            .position(|&index| index == target)
+            .expect("the target is being visited");
+        let members = self.stack[start..].to_vec();
+        let mut cycle: Vec<String> = members
+            .iter()
+            .map(|&index| self.entries[index].key.clone())
+            .collect();
+        cycle.push(self.entries[target].key.clone());
+        for index in members {
+            self.results[index] = Some(Err(Failure::Cycle(cycle.clone())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_str(input: &str) -> Vec<std::result::Result<String, String>> {
+        let entries: Vec<Pending> = input
+            .lines()
+            .filter_map(|line| parse::parse_entry(line).unwrap())
+            .map(|entry| Pending {
+                empty: entry.value_span.is_empty(),
+                key: entry.key,
+                value: entry.value,
+            })
+            .collect();
+        let env: HashMap<String, String> = vec![("RESOLVE_ENV".to_owned(), "env".to_owned())]
+            .into_iter()
+            .collect();
+        resolve(&entries, &env, SubstitutionPolicy::EnvFirst)
+            .into_iter()
+            .map(|result| result.map_err(|err| err.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_forward_references() {
+        assert_eq!(
+            resolve_str("A=${B}-${C}\nB=${C}b\nC=c\nD=${RESOLVE_ENV}\nRESOLVE_ENV=file\n"),
+            vec![
+                Ok("cb-c".to_owned()),
+                Ok("cb".to_owned()),
+                Ok("c".to_owned()),
+                Ok("env".to_owned()),
+                Ok("file".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_earlier_definitions_win() {
+        assert_eq!(
+            resolve_str("A=1\nA=${A}2\nB=${A}\nC=${D-unset}${E:-empty}\nE=\n"),
+            vec![
+                Ok("1".to_owned()),
+                Ok("12".to_owned()),
+                Ok("12".to_owned()),
+                Ok("unsetempty".to_owned()),
+                Ok(String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        assert_eq!(
+            resolve_str("A=${B}\nB=${C}\nC=${A}\nD=${A:-none}\nE=${E}\n"),
+            vec![
+                Err("Reference cycle: A -> B -> C -> A".to_owned()),
+                Err("Reference cycle: A -> B -> C -> A".to_owned()),
+                Err("Reference cycle: A -> B -> C -> A".to_owned()),
+                Ok("none".to_owned()),
+                Err("Reference cycle: E -> E".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::document::{Document, DocumentLine};
+use crate::errors::*;
+
+/// The variables expected by an application, read from the annotations of an example file.
+///
+/// Annotations are comment lines starting with `@`, right above a key:
+///
+/// ```sh
+/// # @required @type=url
+/// DATABASE_URL=
+/// # @pattern=^[a-z]+$ @default=info
+/// LOG_LEVEL=
+/// ```
+///
+/// The annotations are `@required`, `@optional`, `@type=` followed by `string`, `int`,
+/// `float`, `bool` or `url`, `@pattern=` followed by a regular expression without spaces,
+/// which must match the whole value, and `@default=` followed by a value without spaces. Keys
+/// are required unless marked `@optional` or given a default; a key cannot be both
+/// `@required` and given a default.
+///
+/// # Examples
+/// ```no_run
+/// use dotenv_rs::Schema;
+///
+/// let schema = Schema::from_path(".env.example").unwrap();
+/// let mut vars = dotenv_rs::vars().collect();
+/// schema.apply_defaults(&mut vars);
+/// for violation in schema.validate(&vars) {
+///     eprintln!("{}", violation);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    keys: Vec<KeySchema>,
+}
+
+/// What `Schema` expects of a variable.
+#[derive(Clone, Debug)]
+pub struct KeySchema {
+    pub name: String,
+    pub required: bool,
+    pub value_type: ValueType,
+    /// The pattern as written, which must match the whole value.
+    pub pattern: Option<String>,
+    pub default: Option<String>,
+    regex: Option<Regex>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ValueType {
+    String,
+    Int,
+    Float,
+    /// One of `true`, `yes`, `on`, `1`, `false`, `no`, `off` or `0`, as for `var_bool`.
+    Bool,
+    /// An absolute URL, such as `postgres://localhost/db`.
+    Url,
+}
+
+impl ValueType {
+    fn from_name(name: &str) -> Option<ValueType> {
+        match name {
+            "string" => Some(ValueType::String),
+            "int" => Some(ValueType::Int),
+            "float" => Some(ValueType::Float),
+            "bool" => Some(ValueType::Bool),
+            "url" => Some(ValueType::Url),
+            _ => None,
+        }
+    }
+
+    fn accepts(self, value: &str) -> bool {
+        let value = value.trim();
+        match self {
+            ValueType::String => true,
+            ValueType::Int => value.parse::<i64>().is_ok(),
+            ValueType::Float => value.parse::<f64>().is_ok(),
+            ValueType::Bool => matches!(
+                value.to_ascii_lowercase().as_str(),
+                "true" | "yes" | "on" | "1" | "false" | "no" | "off" | "0"
+            ),
+            ValueType::Url => match value.split_once("://") {
+                Some((scheme, rest)) => {
+                    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                        && scheme
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                        && !rest.is_empty()
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            ValueType::String => "string",
+            ValueType::Int => "int",
+            ValueType::Float => "float",
+            ValueType::Bool => "bool",
+            ValueType::Url => "url",
+        })
+    }
+}
+
+/// A variable that does not meet its `KeySchema`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Violation {
+    pub key: String,
+    pub kind: ViolationKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ViolationKind {
+    /// A required variable is unset or empty.
+    Missing,
+    /// The value cannot be read as the expected type.
+    InvalidType(ValueType),
+    /// The value does not match the given pattern.
+    PatternMismatch(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ViolationKind::Missing => write!(fmt, "{} is required", self.key),
+            ViolationKind::InvalidType(value_type) => {
+                write!(fmt, "{} is not a valid {}", self.key, value_type)
+            }
+            ViolationKind::PatternMismatch(pattern) => {
+                write!(fmt, "{} does not match the pattern {}", self.key, pattern)
+            }
+        }
+    }
+}
+
+impl Schema {
+    /// Parses the text of an annotated example file.
+    pub fn parse(input: &str) -> Result<Schema> {
+        let document = Document::parse(input)?;
+        let mut keys = Vec::new();
+        let mut annotations: Vec<(usize, &str)> = Vec::new();
+        let mut line_number = 1;
+        for line in document.lines() {
+            match line {
+                DocumentLine::Blank(_) | DocumentLine::Include(_) => annotations.clear(),
+                DocumentLine::Comment(raw) => {
+                    let text = raw.trim_start()[1..].trim();
+                    if text.starts_with('@') {
+                        annotations.extend(text.split_whitespace().map(|a| (line_number, a)));
+                    }
+                }
+                DocumentLine::Entry(entry) => {
+                    keys.push(KeySchema::from_annotations(entry.key(), &annotations)?);
+                    annotations.clear();
+                }
+            }
+            line_number += line.raw().matches('\n').count();
+        }
+        Ok(Schema { keys })
+    }
+
+    /// Reads and parses the annotated example file at the specified path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Schema> {
+        let input = fs::read_to_string(path).map_err(Error::Io)?;
+        Schema::parse(&input)
+    }
+
+    pub fn keys(&self) -> &[KeySchema] {
+        &self.keys
+    }
+
+    /// Checks `vars` against the schema, returning every violation found. A variable with a
+    /// default is never missing.
+    pub fn validate(&self, vars: &HashMap<String, String>) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for key in &self.keys {
+            let value = match vars.get(&key.name).filter(|value| !value.is_empty()) {
+                Some(value) => value,
+                None if key.default.is_some() => continue,
+                None if key.required => {
+                    violations.push(key.violation(ViolationKind::Missing));
+                    continue;
+                }
+                None => continue,
+            };
+            if !key.value_type.accepts(value) {
+                violations.push(key.violation(ViolationKind::InvalidType(key.value_type)));
+            }
+            if let (Some(pattern), Some(regex)) = (&key.pattern, &key.regex) {
+                if !regex.is_match(value) {
+                    violations.push(key.violation(ViolationKind::PatternMismatch(pattern.clone())));
+                }
+            }
+        }
+        violations
+    }
+
+    /// Sets the variables that are unset or empty in `vars` to their default, if any.
+    pub fn apply_defaults(&self, vars: &mut HashMap<String, String>) {
+        for key in &self.keys {
+            if let Some(default) = &key.default {
+                let value = vars.entry(key.name.clone()).or_default();
+                if value.is_empty() {
+                    value.clone_from(default);
+                }
+            }
+        }
+    }
+}
+
+impl KeySchema {
+    fn from_annotations(name: &str, annotations: &[(usize, &str)]) -> Result<KeySchema> {
+        let mut key = KeySchema {
+            name: name.to_owned(),
+            required: true,
+            value_type: ValueType::String,
+            pattern: None,
+            default: None,
+            regex: None,
+        };
+        let mut optional = false;
+        // the line of an explicit `@required`, which a default would contradict
+        let mut required_line = None;
+        for &(line, annotation) in annotations {
+            let error = |message: String| Error::InvalidSchema { line, message };
+            let (name, argument) = match annotation[1..].split_once('=') {
+                Some((name, argument)) => (name, Some(argument)),
+                None => (&annotation[1..], None),
+            };
+            match (name, argument) {
+                ("required", None) => {
+                    optional = false;
+                    required_line = Some(line);
+                }
+                ("optional", None) => {
+                    optional = true;
+                    required_line = None;
+                }
+                ("type", Some(value_type)) => {
+                    key.value_type = ValueType::from_name(value_type)
+                        .ok_or_else(|| error(format!("unknown type `{}`", value_type)))?;
+                }
+                ("pattern", Some(pattern)) => {
+                    let anchored = format!("^(?:{})$", pattern);
+                    key.regex = Some(Regex::new(&anchored).map_err(|err| error(err.to_string()))?);
+                    key.pattern = Some(pattern.to_owned());
+                }
+                ("default", Some(default)) => key.default = Some(default.to_owned()),
+                _ => return Err(error(format!("invalid annotation `{}`", annotation))),
+            }
+        }
+        if let (Some(line), Some(_)) = (required_line, &key.default) {
+            return Err(Error::InvalidSchema {
+                line,
+                message: String::from("`@required` conflicts with `@default`"),
+            });
+        }
+        key.required = !optional && key.default.is_none();
+        Ok(key)
+    }
+
+    fn violation(&self, kind: ViolationKind) -> Violation {
+        Violation {
+            key: self.name.clone(),
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "# the database
+# @required @type=url
+DATABASE_URL=
+# @type=int @default=8080
+PORT=
+# @pattern=^[a-z]+$ @optional
+LOG_LEVEL=
+
+# @type=bool
+DEBUG=
+";
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let schema = Schema::parse(EXAMPLE).unwrap();
+        let keys = schema.keys();
+        assert_eq!(keys.len(), 4);
+        assert!(keys[0].required);
+        assert_eq!(keys[0].value_type, ValueType::Url);
+        assert!(!keys[1].required);
+        assert_eq!(keys[1].default.as_deref(), Some("8080"));
+        assert!(!keys[2].required);
+        assert_eq!(keys[2].pattern.as_deref(), Some("^[a-z]+$"));
+        assert!(keys[3].required);
+        assert_eq!(keys[3].value_type, ValueType::Bool);
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = Schema::parse(EXAMPLE).unwrap();
+        let violations = schema.validate(&vars(&[
+            ("DATABASE_URL", "localhost"),
+            ("PORT", "eighty"),
+            ("LOG_LEVEL", "Info"),
+        ]));
+        assert_eq!(
+            violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "DATABASE_URL is not a valid url",
+                "PORT is not a valid int",
+                "LOG_LEVEL does not match the pattern ^[a-z]+$",
+                "DEBUG is required",
+            ]
+        );
+
+        let mut valid = vars(&[("DATABASE_URL", "postgres://db/app"), ("DEBUG", "yes")]);
+        assert!(schema.validate(&valid).is_empty());
+        schema.apply_defaults(&mut valid);
+        assert_eq!(valid["PORT"], "8080");
+        assert!(!valid.contains_key("LOG_LEVEL"));
+    }
+
+    #[test]
+    fn test_invalid_annotations() {
+        for (input, message) in [
+            ("# @type=date\nA=\n", "unknown type `date`"),
+            ("# @mandatory\nA=\n", "invalid annotation `@mandatory`"),
+            ("\n# @default\nA=\n", "invalid annotation `@default`"),
+            (
+                "# @required\n# @default=1\nA=\n",
+                "`@required` conflicts with `@default`",
+            ),
+        ] {
+            match Schema::parse(input).unwrap_err() {
+                Error::InvalidSchema {
+                    line,
+                    message: actual,
+                } => {
+                    assert_eq!(actual, message);
+                    assert_eq!(line, if input.starts_with('\n') { 2 } else { 1 });
+                }
+                err => panic!("unexpected error: {}", err),
+            }
+        }
+        assert!(Schema::parse("# @pattern=[\nA=\n").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use std::io::Write;
+
+use crate::errors::*;
+use crate::parse;
+
+/// Writes variables as the text of a .env file, one `KEY=value` line each.
+///
+/// Each value gets the lightest quoting that reads back to the same text: none when it is
+/// made of plain characters, single quotes when it has no `'` or newline, and double quotes
+/// with `\n`, `\"`, `\$` and `\\` escapes otherwise. Values are never substituted when read
+/// back. Fails with `Error::InvalidKey` on a name that is not valid in a .env file.
+///
+/// # Examples
+/// ```
+/// let vars = vec![("NAME", "world"), ("GREETING", "hello $NAME")];
+/// let text = dotenv_rs::to_string(vars).unwrap();
+/// assert_eq!(text, "NAME=world\nGREETING='hello $NAME'\n");
+/// ```
+pub fn to_string<I, K, V>(vars: I) -> Result<String>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut output = String::new();
+    for (key, value) in vars {
+        output.push_str(&line(key.as_ref(), value.as_ref())?);
+    }
+    Ok(output)
+}
+
+/// Like `to_string`, but writes to `writer`.
+pub fn to_writer<W, I, K, V>(mut writer: W, vars: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    for (key, value) in vars {
+        let line = line(key.as_ref(), value.as_ref())?;
+        writer.write_all(line.as_bytes()).map_err(Error::Io)?;
+    }
+    Ok(())
+}
+
+fn line(key: &str, value: &str) -> Result<String> {
+    if !parse::is_valid_key(key) {
+        return Err(Error::InvalidKey {
+            key: key.to_owned(),
+            path: None,
+            line: None,
+        });
+    }
+    Ok(format!("{}={}\n", key, quote(value)))
+}
+
+/// Quotes `value` as lightly as possible.
+fn quote(value: &str) -> String {
+    let plain = |c: char| !(c.is_whitespace() || c.is_control() || "'\"\\$".contains(c));
+    // a leading `#` would start a comment
+    if !value.starts_with('#') && value.chars().all(plain) {
+        value.to_owned()
+    } else if !value.contains(['\'', '\n']) {
+        format!("'{}'", value)
+    } else {
+        double_quote(value)
+    }
+}
+
+fn double_quote(value: &str) -> String {
+    let mut output = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\n' => output.push_str("\\n"),
+            '"' | '$' | '\\' => {
+                output.push('\\');
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::iter::Iter;
+
+    #[test]
+    fn test_quoting() {
+        let text = to_string(vec![
+            ("BARE", "value#1"),
+            ("EMPTY", ""),
+            ("SPACES", "a b"),
+            ("DOLLAR", "$HOME"),
+            ("QUOTE", "it's $HOME"),
+            ("LINES", "a\nb"),
+            ("COMMENT", "#no"),
+        ])
+        .unwrap();
+        assert_eq!(
+            text,
+            "BARE=value#1
+EMPTY=
+SPACES='a b'
+DOLLAR='$HOME'
+QUOTE=\"it's \\$HOME\"
+LINES=\"a\\nb\"
+COMMENT='#no'
+"
+        );
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        let err = to_string(vec![("1KEY", "value")]).unwrap_err();
+        assert!(matches!(err, Error::InvalidKey { key, .. } if key == "1KEY"));
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(key in "[A-Za-z_][A-Za-z0-9_.]{0,16}", value in any::<String>()) {
+            let text = to_string(vec![(&key, &value)]).unwrap();
+            let vars: Vec<(String, String)> = Iter::new(text.as_bytes())
+                .collect::<Result<_>>()
+                .unwrap();
+            prop_assert_eq!(vars, vec![(key, value)]);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn dotenv_rs(dir: &std::path::Path, args: &[&str]) -> (bool, String) {
+    let path = format!(
+        "{}:{}",
+        dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_dotenv_rs"))
+        .current_dir(dir)
+        .env("PATH", path)
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_file_option() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".env"), "CLI_DEFAULT=1\n").unwrap();
+    fs::write(
+        dir.path().join("custom.env"),
+        "CLI_CUSTOM=1\nCLI_CUSTOM=2\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join(".env.example"), "CLI_DEFAULT=\n").unwrap();
+
+    let (success, output) = dotenv_rs(dir.path(), &["-f", "custom.env", "tool", "check"]);
+    assert!(success);
+    assert!(output.contains("custom.env:2"), "{}", output);
+
+    let (success, output) = dotenv_rs(dir.path(), &["-f", "custom.env", "tool", "diff-example"]);
+    assert!(!success);
+    assert_eq!(output, "missing: CLI_DEFAULT\nextra: CLI_CUSTOM\n");
+
+    let (success, output) = dotenv_rs(dir.path(), &["tool", "diff-example"]);
+    assert!(success);
+    assert_eq!(output, "");
+}
+
+#[test]
+fn test_external_command() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".env"), "CLI_NAME=world\n").unwrap();
+    // a program named like a command of the tool
+    let program = dir.path().join("check");
+    fs::write(&program, "#!/bin/sh\necho \"hello $CLI_NAME\"\n").unwrap();
+    fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let (success, output) = dotenv_rs(dir.path(), &["check"]);
+    assert!(success);
+    assert_eq!(output, "hello world\n");
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use dotenv_rs::*;
+use std::env;
+use std::ffi::OsStr;
+use std::process::Command;
+
+use crate::common::*;
+
+fn command_env<'a>(command: &'a Command, key: &str) -> Option<&'a OsStr> {
+    command
+        .get_envs()
+        .find(|(name, _)| *name == OsStr::new(key))
+        .and_then(|(_, value)| value)
+}
+
+#[test]
+fn test_apply_to_command() {
+    env::set_var("COMMAND_PARENT", "parent");
+    let dir = tempdir_with_dotenv(
+        "COMMAND_KEY=value
+COMMAND_PARENT=from_file
+COMMAND_DERIVED=${COMMAND_PARENT}_child
+",
+    )
+    .unwrap();
+    let path = dir.path().join(".env");
+
+    let mut command = Command::new("true");
+    apply_to_command(&mut command, &path).unwrap();
+    assert_eq!(
+        command_env(&command, "COMMAND_KEY"),
+        Some(OsStr::new("value"))
+    );
+    assert_eq!(command_env(&command, "COMMAND_PARENT"), None);
+    assert_eq!(
+        command_env(&command, "COMMAND_DERIVED"),
+        Some(OsStr::new("parent_child"))
+    );
+    assert!(env::var("COMMAND_KEY").is_err());
+
+    let mut command = Command::new("true");
+    command.env_file(&path).unwrap().arg("--flag");
+    assert_eq!(
+        command_env(&command, "COMMAND_KEY"),
+        Some(OsStr::new("value"))
+    );
+
+    assert!(Command::new("true")
+        .env_file(dir.path().join(".env.missing"))
+        .unwrap_err()
+        .not_found());
+
+    dir.close().unwrap();
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use dotenv_rs::*;
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+use tempfile::tempdir;
+
+use crate::common::*;
+
+fn write_file(name: &str, text: &str) {
+    let mut file = File::create(name).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+}
+
+#[test]
+fn test_dotenv_flow() {
+    let dir = tempdir_with_dotenv(
+        "FLOW_NAME=base
+FLOW_ENV=base
+FLOW_LOCAL=base
+FLOW_MODE_LOCAL=base
+",
+    )
+    .unwrap();
+    write_file(".env.local", "FLOW_LOCAL=local\nFLOW_MODE_LOCAL=local\n");
+    write_file(
+        ".env.development",
+        "FLOW_ENV=development\nFLOW_URL=${FLOW_NAME}.dev\n",
+    );
+    write_file(
+        ".env.development.local",
+        "FLOW_MODE_LOCAL=development_local\n",
+    );
+    write_file(".env.test", "FLOW_ENV=test\n");
+
+    let paths = dotenv_flow("development").unwrap();
+    assert_eq!(
+        paths,
+        vec![
+            dir.path().join(".env"),
+            dir.path().join(".env.local"),
+            dir.path().join(".env.development"),
+            dir.path().join(".env.development.local"),
+        ]
+    );
+    assert_eq!(env::var("FLOW_NAME").unwrap(), "base");
+    assert_eq!(env::var("FLOW_LOCAL").unwrap(), "local");
+    assert_eq!(env::var("FLOW_ENV").unwrap(), "development");
+    assert_eq!(env::var("FLOW_MODE_LOCAL").unwrap(), "development_local");
+    assert_eq!(env::var("FLOW_URL").unwrap(), "base.dev");
+
+    dir.close().unwrap();
+}
+
+#[test]
+fn test_dotenv_flow_missing_files() {
+    let dir = tempdir().unwrap();
+
+    // none of the family exists
+    let err = DotenvLoader::new()
+        .flow("staging")
+        .search_from(dir.path())
+        .load()
+        .unwrap_err();
+    assert!(err.not_found());
+
+    File::create(dir.path().join(".env"))
+        .and_then(|mut file| file.write_all(b"FLOW_ONLY_BASE=base\n"))
+        .unwrap();
+    let paths = DotenvLoader::new()
+        .flow("staging")
+        .search_from(dir.path())
+        .load()
+        .unwrap();
+    assert_eq!(paths, vec![dir.path().join(".env")]);
+    assert_eq!(env::var("FLOW_ONLY_BASE").unwrap(), "base");
+
+    dir.close().unwrap();
+}
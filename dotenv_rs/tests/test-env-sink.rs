@@ -0,0 +1,64 @@
+mod common;
+
+use dotenv_rs::*;
+use std::collections::HashMap;
+use std::env;
+use std::process::Command;
+
+use crate::common::*;
+
+#[test]
+fn test_load_into_sink() {
+    let dir = tempdir_with_dotenv(
+        "SINK_KEY=value
+SINK_EXISTING=from_file
+SINK_PATH=${SINK_HOME}/bin
+",
+    )
+    .unwrap();
+
+    let mut source = HashMap::new();
+    source.insert("SINK_HOME".to_owned(), "/home/sink".to_owned());
+    let mut vars = HashMap::new();
+    vars.insert("SINK_EXISTING".to_owned(), "existing".to_owned());
+    DotenvLoader::new()
+        .source(source)
+        .load_into(&mut vars)
+        .unwrap();
+
+    assert_eq!(vars["SINK_KEY"], "value");
+    assert_eq!(vars["SINK_EXISTING"], "existing");
+    assert_eq!(vars["SINK_PATH"], "/home/sink/bin");
+    assert!(env::var("SINK_KEY").is_err());
+
+    let mut command = Command::new("true");
+    DotenvLoader::new().load_into(&mut command).unwrap();
+    assert_eq!(command.get("SINK_KEY"), Some("value".to_owned()));
+    assert_eq!(command.get("SINK_PATH"), Some("/bin".to_owned()));
+    assert!(env::var("SINK_KEY").is_err());
+
+    dir.close().unwrap();
+}
+
+/// A source holding a single variable.
+struct OneVar;
+
+impl EnvSource for OneVar {
+    fn get(&self, key: &str) -> Option<String> {
+        if key == "SINK_ONE" {
+            Some("one".to_owned())
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_var_from_source() {
+    assert_eq!(var_from(&OneVar, "SINK_ONE").unwrap(), "one");
+    assert!(matches!(
+        var_from(&OneVar, "SINK_OTHER"),
+        Err(Error::EnvVar(env::VarError::NotPresent))
+    ));
+    assert!(env::var("SINK_ONE").is_err());
+}
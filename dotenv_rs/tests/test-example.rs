@@ -0,0 +1,64 @@
+mod common;
+
+use dotenv_rs::*;
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+
+use crate::common::*;
+
+fn write_file(name: &str, text: &str) {
+    let mut file = File::create(name).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+}
+
+#[test]
+fn test_check_against_example() {
+    let dir = tempdir_with_dotenv(
+        "EXAMPLE_URL=http://localhost
+EXAMPLE_EMPTY=
+EXAMPLE_EXTRA=1
+",
+    )
+    .unwrap();
+    write_file(
+        ".env.example",
+        "EXAMPLE_URL=
+EXAMPLE_EMPTY=
+EXAMPLE_MISSING=${EXAMPLE_URL:?}
+EXAMPLE_PROVIDED=
+",
+    );
+
+    let report = check_against_example(".env", ".env.example").unwrap();
+    assert_eq!(
+        report,
+        ExampleReport {
+            missing: vec!["EXAMPLE_MISSING".to_owned(), "EXAMPLE_PROVIDED".to_owned()],
+            extra: vec!["EXAMPLE_EXTRA".to_owned()],
+            empty: vec!["EXAMPLE_EMPTY".to_owned()],
+        }
+    );
+    assert!(!report.is_ok());
+
+    // the loader only requires the variables to be set, from the files or the environment
+    env::set_var("EXAMPLE_PROVIDED", "from_env");
+    let err = DotenvLoader::new()
+        .require_example(".env.example")
+        .load()
+        .unwrap_err();
+    match err {
+        Error::MissingRequired { keys } => assert_eq!(keys, vec!["EXAMPLE_MISSING"]),
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(env::var("EXAMPLE_URL").is_err());
+
+    env::set_var("EXAMPLE_MISSING", "from_env");
+    DotenvLoader::new()
+        .require_example(".env.example")
+        .load()
+        .unwrap();
+    assert_eq!(env::var("EXAMPLE_URL").unwrap(), "http://localhost");
+
+    dir.close().unwrap();
+}
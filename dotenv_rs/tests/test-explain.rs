@@ -0,0 +1,88 @@
+mod common;
+
+use dotenv_rs::*;
+use std::env;
+
+use crate::common::*;
+
+#[test]
+fn test_explain() {
+    env::set_var("EXPLAIN_PORT", "from_env");
+    let dir = tempdir_with_dotenv(
+        "EXPLAIN_HOST=localhost
+EXPLAIN_PORT=5432
+EXPLAIN_URL=db://${EXPLAIN_HOST}:${EXPLAIN_PORT}/${EXPLAIN_NAME:-app}
+EXPLAIN_DB=${EXPLAIN_URL}
+",
+    )
+    .unwrap();
+    let path = dir.path().join(".env");
+
+    let explanation = explain("EXPLAIN_DB").unwrap();
+    assert_eq!(
+        explanation.value.as_deref(),
+        Some("db://localhost:from_env/app")
+    );
+    assert_eq!(explanation.source, ValueSource::File);
+    assert!(!explanation.shadowed);
+    let definition = explanation.definition.unwrap();
+    assert_eq!((definition.path, definition.line), (Some(path.clone()), 4));
+    let steps: Vec<(&str, usize, Option<&str>, ValueSource)> = explanation
+        .references
+        .iter()
+        .map(|step| {
+            (
+                step.name.as_str(),
+                step.depth,
+                step.value.as_deref(),
+                step.source,
+            )
+        })
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            (
+                "EXPLAIN_URL",
+                1,
+                Some("db://localhost:from_env/app"),
+                ValueSource::File
+            ),
+            ("EXPLAIN_HOST", 2, Some("localhost"), ValueSource::File),
+            (
+                "EXPLAIN_PORT",
+                2,
+                Some("from_env"),
+                ValueSource::Environment
+            ),
+            ("EXPLAIN_NAME", 2, None, ValueSource::Unset),
+        ]
+    );
+    assert_eq!(
+        explanation.references[1].definition.as_ref().unwrap().line,
+        1
+    );
+
+    let explanation = explain("EXPLAIN_PORT").unwrap();
+    assert_eq!(explanation.value.as_deref(), Some("from_env"));
+    assert_eq!(explanation.source, ValueSource::Environment);
+    assert!(explanation.shadowed);
+    assert_eq!(explanation.definition.unwrap().value, "5432");
+
+    let explanation = DotenvLoader::new()
+        .override_existing(true)
+        .explain("EXPLAIN_PORT")
+        .unwrap();
+    assert_eq!(explanation.value.as_deref(), Some("5432"));
+    assert!(!explanation.shadowed);
+
+    let explanation = explain("EXPLAIN_MISSING").unwrap();
+    assert_eq!(explanation.source, ValueSource::Unset);
+    assert_eq!(explanation.definition, None);
+
+    // nothing was loaded
+    assert!(env::var("EXPLAIN_HOST").is_err());
+    assert_eq!(env::var("EXPLAIN_PORT").unwrap(), "from_env");
+
+    dir.close().unwrap();
+}
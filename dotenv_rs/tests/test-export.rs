@@ -0,0 +1,28 @@
+mod common;
+
+use dotenv_rs::*;
+
+use crate::common::*;
+
+#[test]
+fn test_export_vars() {
+    let dir = tempdir_with_dotenv(
+        "EXPORT_B='it'\"'\"'s'
+EXPORT_A=\"${EXPORT_B} here\"
+",
+    )
+    .unwrap();
+
+    assert_eq!(
+        export_vars(".env", ExportFormat::Posix).unwrap(),
+        "export EXPORT_A='it'\\''s here'\nexport EXPORT_B='it'\\''s'\n"
+    );
+    assert_eq!(
+        export_vars(".env", ExportFormat::Docker).unwrap(),
+        "EXPORT_A=it's here\nEXPORT_B=it's\n"
+    );
+    assert_eq!(ExportFormat::from_name("fish"), Some(ExportFormat::Fish));
+    assert_eq!(ExportFormat::from_name("csh"), None);
+
+    dir.close().unwrap();
+}
@@ -0,0 +1,50 @@
+#![cfg(all(feature = "json", feature = "properties"))]
+
+use dotenv_rs::*;
+use std::env;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_from_path_format() {
+    let dir = tempdir().unwrap();
+    let json = dir.path().join("config.json");
+    fs::write(
+        &json,
+        r#"{"FORMAT_DB": {"URL": "postgres://db", "POOL": 5}, "FORMAT_HOSTS": ["a", "b"]}"#,
+    )
+    .unwrap();
+    from_path_format(&json, Format::Json).unwrap();
+    assert_eq!(env::var("FORMAT_DB__URL").unwrap(), "postgres://db");
+    assert_eq!(env::var("FORMAT_DB__POOL").unwrap(), "5");
+    assert_eq!(env::var("FORMAT_HOSTS__1").unwrap(), "b");
+
+    let properties = dir.path().join("app.properties");
+    fs::write(
+        &properties,
+        "format.name = app\nformat.db.url=other\nignored=1\n",
+    )
+    .unwrap();
+    let mut vars = std::collections::HashMap::new();
+    let report = DotenvLoader::new()
+        .path(&properties)
+        .format(Format::Properties)
+        .prefix("format.")
+        .load_into_with_report(&mut vars)
+        .unwrap();
+    assert_eq!(vars.len(), 2);
+    assert_eq!(vars["format.name"], "app");
+    assert_eq!(report.keys[2].outcome, LoadOutcome::Filtered);
+    assert_eq!(report.keys[1].line, 2);
+
+    fs::write(&json, "{\"FORMAT_BROKEN\": ").unwrap();
+    match from_path_format(&json, Format::Json).unwrap_err() {
+        Error::InvalidDocument { format, path, .. } => {
+            assert_eq!(format, Format::Json);
+            assert_eq!(path, Some(json));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+
+    dir.close().unwrap();
+}
@@ -0,0 +1,82 @@
+use dotenv_rs::*;
+use std::collections::HashMap;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_forward_references() {
+    let dir = tempdir().unwrap();
+    let env_path = dir.path().join(".env");
+    let local_path = dir.path().join(".env.local");
+    fs::write(
+        &env_path,
+        "FORWARD_URL=http://${FORWARD_HOST}:${FORWARD_PORT}\nFORWARD_HOST=localhost\n",
+    )
+    .unwrap();
+    fs::write(&local_path, "FORWARD_PORT=8080\n").unwrap();
+
+    let mut vars = HashMap::new();
+    DotenvLoader::new()
+        .path(&env_path)
+        .path(&local_path)
+        .load_into(&mut vars)
+        .unwrap();
+    assert_eq!(vars["FORWARD_URL"], "http://:");
+
+    let loader = DotenvLoader::new()
+        .path(&env_path)
+        .path(&local_path)
+        .forward_references(true);
+    let mut vars = HashMap::new();
+    let report = loader.load_into_with_report(&mut vars).unwrap();
+    assert_eq!(vars["FORWARD_URL"], "http://localhost:8080");
+    let lines: Vec<(&str, usize)> = report
+        .keys
+        .iter()
+        .map(|key| (key.key.as_str(), key.line))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![("FORWARD_URL", 1), ("FORWARD_HOST", 2), ("FORWARD_PORT", 1)]
+    );
+
+    let explanation = loader.explain("FORWARD_URL").unwrap();
+    let references: Vec<(&str, Option<&str>)> = explanation
+        .references
+        .iter()
+        .map(|step| (step.name.as_str(), step.value.as_deref()))
+        .collect();
+    assert_eq!(
+        references,
+        vec![
+            ("FORWARD_HOST", Some("localhost")),
+            ("FORWARD_PORT", Some("8080"))
+        ]
+    );
+}
+
+#[test]
+fn test_reference_cycle() {
+    let dir = tempdir().unwrap();
+    let env_path = dir.path().join(".env");
+    fs::write(
+        &env_path,
+        "CYCLE_A=${CYCLE_B}\nCYCLE_B=x${CYCLE_A}\nCYCLE_C=c\n",
+    )
+    .unwrap();
+
+    let loader = DotenvLoader::new().path(&env_path).forward_references(true);
+    match loader.load_into(&mut HashMap::new()).unwrap_err() {
+        Error::ReferenceCycle { cycle, path, line } => {
+            assert_eq!(cycle, vec!["CYCLE_A", "CYCLE_B", "CYCLE_A"]);
+            assert_eq!(path, Some(env_path.clone()));
+            assert_eq!(line, Some(1));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+
+    let mut vars = HashMap::new();
+    loader.strict(false).load_into(&mut vars).unwrap();
+    assert_eq!(vars.len(), 1);
+    assert_eq!(vars["CYCLE_C"], "c");
+}
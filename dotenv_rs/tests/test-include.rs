@@ -0,0 +1,101 @@
+use dotenv_rs::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+#[test]
+fn test_include() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("shared")).unwrap();
+    fs::create_dir(dir.path().join("app")).unwrap();
+    fs::write(
+        dir.path().join("shared/base.env"),
+        "INCLUDE_HOST=db\nsource common.env\nINCLUDE_PORT=1\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("shared/common.env"), "INCLUDE_USER=admin\n").unwrap();
+    let env_path = dir.path().join("app/.env");
+    fs::write(
+        &env_path,
+        "# @include ../shared/base.env
+INCLUDE_PORT=2
+INCLUDE_URL=${INCLUDE_USER}@${INCLUDE_HOST}:${INCLUDE_PORT}
+",
+    )
+    .unwrap();
+
+    let mut vars = HashMap::new();
+    let report = DotenvLoader::new()
+        .path(&env_path)
+        .load_into_with_report(&mut vars)
+        .unwrap();
+    assert_eq!(vars["INCLUDE_URL"], "admin@db:2");
+    assert_eq!(vars["INCLUDE_PORT"], "2");
+    let sources: Vec<(&str, PathBuf, usize)> = report
+        .keys
+        .iter()
+        .map(|key| {
+            let path = key.path.as_ref().unwrap().strip_prefix(dir.path());
+            (key.key.as_str(), path.unwrap().to_path_buf(), key.line)
+        })
+        .collect();
+    assert_eq!(
+        sources,
+        vec![
+            ("INCLUDE_HOST", PathBuf::from("app/../shared/base.env"), 1),
+            ("INCLUDE_USER", PathBuf::from("app/../shared/common.env"), 1),
+            ("INCLUDE_PORT", PathBuf::from("app/../shared/base.env"), 3),
+            ("INCLUDE_PORT", PathBuf::from("app/.env"), 2),
+            ("INCLUDE_URL", PathBuf::from("app/.env"), 3),
+        ]
+    );
+
+    dir.close().unwrap();
+}
+
+#[test]
+fn test_include_errors() {
+    let dir = tempdir().unwrap();
+    let env_path = dir.path().join(".env");
+    let base_path = dir.path().join("base.env");
+    fs::write(&env_path, "A=1\nsource base.env\n").unwrap();
+    fs::write(&base_path, "B=2\n# @include .env\n").unwrap();
+    match DotenvLoader::new().path(&env_path).to_map().unwrap_err() {
+        Error::IncludeCycle { chain } => assert_eq!(
+            chain,
+            vec![env_path.clone(), base_path.clone(), dir.path().join(".env")]
+        ),
+        err => panic!("unexpected error: {}", err),
+    }
+
+    fs::write(&base_path, "B=2\nsource missing.env\nC=\"open\n").unwrap();
+    let (entries, diagnostics) = DotenvLoader::new()
+        .path(&env_path)
+        .iter()
+        .unwrap()
+        .parse_all();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(diagnostics.len(), 2);
+    match &diagnostics[0].error {
+        Error::Include { chain, error } => {
+            assert_eq!(
+                chain,
+                &vec![(Some(env_path.clone()), 2), (Some(base_path.clone()), 2)]
+            );
+            assert!(error.not_found());
+            assert!(error.to_string().contains("missing.env"));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    match &diagnostics[1].error {
+        Error::Include { chain, error } => {
+            assert_eq!(chain, &vec![(Some(env_path.clone()), 2)]);
+            assert!(matches!(&**error, Error::Parse(err) if err.line == 3));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    assert_eq!(diagnostics[1].path, Some(base_path));
+
+    dir.close().unwrap();
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use dotenv_rs::*;
+use std::fs::File;
+use std::io::prelude::*;
+
+use crate::common::*;
+
+#[test]
+fn test_lint_files() {
+    let dir = tempdir_with_dotenv("LINT_BASE=base\nLINT_BASE=again\n").unwrap();
+    let local = dir.path().join(".env.local");
+    File::create(&local)
+        .and_then(|mut file| file.write_all(b"LINT_LOCAL=${LINT_BASE}/${LINT_NOPE}\n"))
+        .unwrap();
+
+    let diagnostics = lint_files(&[dir.path().join(".env"), local.clone()]).unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].path, Some(dir.path().join(".env")));
+    assert_eq!(diagnostics[0].line, 2);
+    assert_eq!(diagnostics[0].kind, LintKind::DuplicateKey);
+    assert_eq!(diagnostics[1].path, Some(local));
+    assert_eq!(diagnostics[1].kind, LintKind::UndefinedVariable);
+    assert_eq!(diagnostics[1].severity(), Severity::Warning);
+    assert!(diagnostics[1].message.contains("LINT_NOPE"));
+
+    assert!(lint_files(&[dir.path().join(".env.missing")])
+        .unwrap_err()
+        .not_found());
+
+    let shared = dir.path().join("shared.env");
+    let service = dir.path().join("service.env");
+    File::create(&shared)
+        .and_then(|mut file| file.write_all(b"LINT_SHARED=shared\n# @include service.env\n"))
+        .unwrap();
+    File::create(&service)
+        .and_then(|mut file| {
+            file.write_all(b"# @include shared.env\nLINT_SERVICE=${LINT_SHARED}/${LINT_UNSET}\n")
+        })
+        .unwrap();
+    let diagnostics = lint_files([&service]).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].message.contains("LINT_UNSET"));
+
+    File::create(&service)
+        .and_then(|mut file| {
+            file.write_all(b"# @include missing.env\nLINT_SERVICE=${LINT_UNSET}\n")
+        })
+        .unwrap();
+    assert!(lint_files([&service]).unwrap().is_empty());
+
+    dir.close().unwrap();
+}
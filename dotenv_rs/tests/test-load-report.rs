@@ -0,0 +1,96 @@
+mod common;
+
+use dotenv_rs::*;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use tempfile::tempdir;
+
+use crate::common::*;
+
+#[test]
+fn test_load_report() {
+    env::set_var("REPORT_EXISTING", "from_env");
+    let dir = tempdir_with_dotenv(
+        "REPORT_NEW=1
+REPORT_EXISTING=2
+OTHER=3
+REPORT_NEW=4
+",
+    )
+    .unwrap();
+    File::create(".env.local")
+        .and_then(|mut file| file.write_all(b"REPORT_NEW=5\n"))
+        .unwrap();
+
+    let report = DotenvLoader::new()
+        .filename(".env")
+        .filename(".env.local")
+        .prefix("REPORT_")
+        .load_with_report()
+        .unwrap();
+    assert_eq!(
+        report.paths,
+        vec![dir.path().join(".env"), dir.path().join(".env.local")]
+    );
+    let outcomes: Vec<(&str, LoadOutcome, usize)> = report
+        .keys
+        .iter()
+        .map(|key| (key.key.as_str(), key.outcome, key.line))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("REPORT_NEW", LoadOutcome::Set, 1),
+            ("REPORT_EXISTING", LoadOutcome::SkippedExisting, 2),
+            ("OTHER", LoadOutcome::Filtered, 3),
+            ("REPORT_NEW", LoadOutcome::SkippedExisting, 4),
+            ("REPORT_NEW", LoadOutcome::Overridden, 1),
+        ]
+    );
+    assert_eq!(report.keys[4].path, Some(dir.path().join(".env.local")));
+    assert_eq!(env::var("REPORT_NEW").unwrap(), "5");
+    assert_eq!(env::var("REPORT_EXISTING").unwrap(), "from_env");
+    assert!(env::var("OTHER").is_err());
+
+    let report = from_filename_iter(".env")
+        .unwrap()
+        .load_override("REPORT_")
+        .unwrap();
+    assert_eq!(report.with_outcome(LoadOutcome::Overridden).count(), 3);
+    assert_eq!(report.with_outcome(LoadOutcome::Filtered).count(), 1);
+    assert_eq!(env::var("REPORT_EXISTING").unwrap(), "2");
+
+    dir.close().unwrap();
+}
+
+#[test]
+fn test_override_reference() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join(".env");
+    fs::write(&path, "A=new\nB=${A}\n").unwrap();
+    let env: HashMap<String, String> = vec![("A".to_owned(), "stale".to_owned())]
+        .into_iter()
+        .collect();
+
+    let mut vars = env.clone();
+    let report = DotenvLoader::new()
+        .path(&path)
+        .source(env.clone())
+        .override_existing(true)
+        .load_into_with_report(&mut vars)
+        .unwrap();
+    assert_eq!(vars["A"], "new");
+    assert_eq!(vars["B"], "new");
+    assert_eq!(report.keys[1].value, "new");
+
+    let mut vars = env.clone();
+    DotenvLoader::new()
+        .path(&path)
+        .source(env)
+        .load_into(&mut vars)
+        .unwrap();
+    assert_eq!(vars["A"], "stale");
+    assert_eq!(vars["B"], "stale");
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use dotenv_rs::*;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+
+use crate::common::*;
+
+fn write_file(name: &str, text: &str) {
+    let mut file = File::create(name).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+}
+
+#[test]
+fn test_loader() {
+    let dir = tempdir_with_dotenv(
+        "LOADER_BASE=base
+LOADER_SHARED=from_env
+LOADER_PATH=${LOADER_BASE}/bin
+",
+    )
+    .unwrap();
+    write_file(
+        ".env.local",
+        "LOADER_SHARED=from_local
+LOADER_LOCAL=${LOADER_BASE}_local
+OTHER_KEY=other
+",
+    );
+    fs::create_dir("child").unwrap();
+
+    // later files take precedence and can refer to the earlier ones
+    let paths = DotenvLoader::new()
+        .filename(".env")
+        .filename(".env.local")
+        .filename(".env.missing")
+        .search_from(dir.path().join("child"))
+        .strict(false)
+        .prefix("LOADER_")
+        .load()
+        .unwrap();
+    assert_eq!(
+        paths,
+        vec![dir.path().join(".env"), dir.path().join(".env.local")]
+    );
+    assert_eq!(env::var("LOADER_SHARED").unwrap(), "from_local");
+    assert_eq!(env::var("LOADER_PATH").unwrap(), "base/bin");
+    assert_eq!(env::var("LOADER_LOCAL").unwrap(), "base_local");
+    assert!(env::var("OTHER_KEY").is_err());
+
+    // a missing file is an error when strict
+    assert!(DotenvLoader::new()
+        .filename(".env.missing")
+        .load()
+        .unwrap_err()
+        .not_found());
+
+    // existing variables are kept unless overriding
+    env::set_var("LOADER_BASE", "existing");
+    DotenvLoader::new().load().unwrap();
+    assert_eq!(env::var("LOADER_BASE").unwrap(), "existing");
+    DotenvLoader::new().override_existing(true).load().unwrap();
+    assert_eq!(env::var("LOADER_BASE").unwrap(), "base");
+
+    let vars = DotenvLoader::new()
+        .path(dir.path().join(".env.local"))
+        .substitution(false)
+        .to_map()
+        .unwrap();
+    let mut expected = HashMap::new();
+    expected.insert(
+        String::from("LOADER_SHARED"),
+        Some(String::from("from_local")),
+    );
+    expected.insert(
+        String::from("LOADER_LOCAL"),
+        Some(String::from("${LOADER_BASE}_local")),
+    );
+    expected.insert(String::from("OTHER_KEY"), Some(String::from("other")));
+    assert_eq!(vars, expected);
+
+    let keys: Vec<String> = DotenvLoader::new()
+        .filename(".env.local")
+        .iter()
+        .unwrap()
+        .map(|item| item.unwrap().0)
+        .collect();
+    assert_eq!(keys, vec!["LOADER_SHARED", "LOADER_LOCAL", "OTHER_KEY"]);
+
+    // the prefix options apply to iteration too
+    let vars: Vec<(String, String)> = DotenvLoader::new()
+        .filename(".env.local")
+        .prefix("LOADER_")
+        .strip_prefix(true)
+        .add_prefix("NEW_")
+        .iter()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        vars,
+        vec![
+            (String::from("NEW_SHARED"), String::from("from_local")),
+            (String::from("NEW_LOCAL"), String::from("base_local")),
+        ]
+    );
+
+    env::set_current_dir(dir.path().parent().unwrap()).unwrap();
+    dir.close().unwrap();
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use dotenv_rs::*;
+use std::{env, fs};
+
+use crate::common::*;
+
+#[test]
+fn test_dotenv_override() {
+    let dir = make_test_dotenv().unwrap();
+
+    env::set_var("TESTKEY", "stale_val");
+    env::set_var("TestKEY", "stale_val");
+
+    dotenv().ok();
+    assert_eq!(env::var("TESTKEY").unwrap(), "stale_val");
+
+    dotenv_override().ok();
+    assert_eq!(env::var("TESTKEY").unwrap(), "test_val");
+    assert_eq!(env::var("TestKEY").unwrap(), "test_val_prefix");
+
+    env::set_var("TESTKEY", "stale_val");
+    from_filename_override(".env").ok();
+    assert_eq!(env::var("TESTKEY").unwrap(), "test_val");
+
+    env::set_var("TESTKEY", "stale_val");
+    let mut path = env::current_dir().unwrap();
+    path.push(".env");
+    from_path_override(&path).ok();
+    assert_eq!(env::var("TESTKEY").unwrap(), "test_val");
+
+    fs::write(&path, "OVERRIDE_A=new\nOVERRIDE_B=${OVERRIDE_A}\n").unwrap();
+    env::set_var("OVERRIDE_A", "stale");
+    dotenv_override().ok();
+    assert_eq!(env::var("OVERRIDE_A").unwrap(), "new");
+    assert_eq!(env::var("OVERRIDE_B").unwrap(), "new");
+
+    env::set_var("OVERRIDE_A", "stale");
+    env::remove_var("OVERRIDE_B");
+    from_path_iter(&path).unwrap().load_override("").unwrap();
+    assert_eq!(env::var("OVERRIDE_A").unwrap(), "new");
+    assert_eq!(env::var("OVERRIDE_B").unwrap(), "new");
+
+    env::set_current_dir(dir.path().parent().unwrap()).unwrap();
+    dir.close().unwrap();
+}
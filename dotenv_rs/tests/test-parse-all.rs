@@ -0,0 +1,43 @@
+use dotenv_rs::*;
+use std::io::Cursor;
+
+#[test]
+fn test_parse_all() {
+    let input = "FIRST=1
+BROKEN LINE
+SECOND=${FIRST}2
+THIRD=a b
+REQUIRED=${PARSE_ALL_UNSET:?must be set}
+LAST='multi
+line'
+UNTERMINATED=\"open
+";
+    let (entries, diagnostics) = Iter::new(Cursor::new(input)).parse_all();
+
+    let entries: Vec<(&str, &str, usize)> = entries
+        .iter()
+        .map(|entry| (entry.key.as_str(), entry.value.as_str(), entry.line))
+        .collect();
+    assert_eq!(
+        entries,
+        vec![
+            ("FIRST", "1", 1),
+            ("SECOND", "12", 3),
+            ("LAST", "multi\nline", 6)
+        ]
+    );
+
+    let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+    assert_eq!(lines, vec![2, 4, 5, 8]);
+    match &diagnostics[1].error {
+        Error::Parse(err) => {
+            assert_eq!(err.kind, ParseErrorKind::UnexpectedCharacter);
+            assert_eq!((err.line, err.column), (4, 9));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(matches!(
+        &diagnostics[2].error,
+        Error::UnsetVariable { name, .. } if name == "PARSE_ALL_UNSET"
+    ));
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use dotenv_rs::*;
+use std::env;
+
+use crate::common::*;
+
+#[test]
+fn test_parse_error() {
+    let dir = tempdir_with_dotenv(
+        "PARSE_ERROR_KEY=1
+PARSE_ERROR_MULTILINE=\"a
+b\"
+PARSE_ERROR_BAD=\"unterminated
+",
+    )
+    .unwrap();
+
+    let path = dir.path().join(".env");
+    match from_path(&path) {
+        Err(Error::Parse(err)) => {
+            assert_eq!(err.path, Some(path));
+            assert_eq!(err.line, 4);
+            assert_eq!(err.column, 17);
+            assert_eq!(err.kind, ParseErrorKind::UnterminatedQuote);
+        }
+        _ => panic!("Expected the file not to be parsed"),
+    }
+
+    let unset = dir.path().join("unset.env");
+    std::fs::write(
+        &unset,
+        "PARSE_ERROR_A=1\n\nPARSE_ERROR_B=${PARSE_ERROR_UNSET:?is needed}\n",
+    )
+    .unwrap();
+    let err = from_path(&unset).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("{}:3: PARSE_ERROR_UNSET: is needed", unset.display())
+    );
+
+    env::set_current_dir(dir.path().parent().unwrap()).unwrap();
+    dir.close().unwrap();
+}
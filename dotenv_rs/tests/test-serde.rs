@@ -0,0 +1,159 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use dotenv_rs::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+
+use crate::common::*;
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    Development,
+    Production,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Database {
+    url: String,
+    pool_size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Config {
+    port: u16,
+    debug: bool,
+    ratio: f64,
+    mode: Mode,
+    hosts: Vec<String>,
+    ports: Vec<u16>,
+    empty_list: Vec<String>,
+    missing: Option<String>,
+    empty: Option<u16>,
+    #[serde(rename = "APP_NAME")]
+    name: String,
+    db: Database,
+}
+
+#[test]
+fn test_from_path_into() {
+    let dir = tempdir_with_dotenv(
+        "PORT=8080
+DEBUG=true
+RATIO=0.5
+MODE=production
+HOSTS=\"a.example.com, b.example.com\"
+PORTS=80,443
+EMPTY_LIST=
+EMPTY=
+APP_NAME=demo
+DB__URL=postgres://localhost
+DB__POOL_SIZE=5
+",
+    )
+    .unwrap();
+
+    let config: Config = from_path_into(dir.path().join(".env")).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            port: 8080,
+            debug: true,
+            ratio: 0.5,
+            mode: Mode::Production,
+            hosts: vec![String::from("a.example.com"), String::from("b.example.com")],
+            ports: vec![80, 443],
+            empty_list: Vec::new(),
+            missing: None,
+            empty: None,
+            name: String::from("demo"),
+            db: Database {
+                url: String::from("postgres://localhost"),
+                pool_size: Some(5),
+            },
+        }
+    );
+    assert!(env::var("PORT").is_err());
+
+    let vars: HashMap<String, String> = from_path_into(dir.path().join(".env")).unwrap();
+    assert_eq!(vars["DB__URL"], "postgres://localhost");
+
+    env::set_current_dir(dir.path().parent().unwrap()).unwrap();
+    dir.close().unwrap();
+}
+
+#[test]
+fn test_deserialize_errors() {
+    let vars = |pairs: &[(&str, &str)]| {
+        EnvDeserializer::new(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        )
+    };
+
+    match Database::deserialize(vars(&[("URL", "x"), ("POOL_SIZE", "many")])) {
+        Err(Error::Deserialize { key, .. }) => assert_eq!(key.as_deref(), Some("POOL_SIZE")),
+        _ => panic!("Expected POOL_SIZE not to be deserialized"),
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Nested {
+        #[allow(dead_code)]
+        db: Database,
+    }
+    match Nested::deserialize(vars(&[("DB__URL", "x"), ("DB__POOL_SIZE", "-1")])) {
+        Err(Error::Deserialize { key, .. }) => assert_eq!(key.as_deref(), Some("DB__POOL_SIZE")),
+        _ => panic!("Expected DB__POOL_SIZE not to be deserialized"),
+    }
+    match Nested::deserialize(vars(&[("DB__POOL_SIZE", "1")])) {
+        Err(err @ Error::Deserialize { .. }) => {
+            assert_eq!(
+                format!("{}", err),
+                "Error deserializing DB: missing field `url`"
+            )
+        }
+        _ => panic!("Expected DB not to be deserialized"),
+    }
+}
+
+#[test]
+fn test_deserialize_bool() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Flags {
+        a: bool,
+        b: bool,
+        c: bool,
+        d: bool,
+    }
+
+    let vars = vec![("A", "1"), ("B", "Yes"), ("C", "on"), ("D", "off")];
+    let flags = Flags::deserialize(EnvDeserializer::new(
+        vars.into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+    ))
+    .unwrap();
+    assert_eq!(
+        flags,
+        Flags {
+            a: true,
+            b: true,
+            c: true,
+            d: false,
+        }
+    );
+
+    #[derive(Debug, Deserialize)]
+    struct Flag {
+        #[allow(dead_code)]
+        a: bool,
+    }
+    let vars = vec![("A".to_string(), "maybe".to_string())];
+    match Flag::deserialize(EnvDeserializer::new(vars)) {
+        Err(Error::Deserialize { key, .. }) => assert_eq!(key.as_deref(), Some("A")),
+        _ => panic!("Expected A not to be deserialized"),
+    }
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use dotenv_rs::*;
+use std::collections::HashMap;
+use std::env;
+
+use crate::common::*;
+
+#[test]
+fn test_strip_prefix() {
+    let dir = tempdir_with_dotenv(
+        "APP_DB_URL=postgres://app
+APP_=empty_name
+WORKER_DB_URL=postgres://worker
+",
+    )
+    .unwrap();
+
+    DotenvLoader::new()
+        .prefix("APP_")
+        .strip_prefix(true)
+        .load()
+        .unwrap();
+    assert_eq!(env::var("DB_URL").unwrap(), "postgres://app");
+    assert!(env::var("APP_DB_URL").is_err());
+    assert!(env::var("WORKER_DB_URL").is_err());
+
+    let vars = DotenvLoader::new()
+        .prefix("WORKER_")
+        .strip_prefix(true)
+        .add_prefix("SVC_")
+        .to_map()
+        .unwrap();
+    let mut expected = HashMap::new();
+    expected.insert(
+        String::from("SVC_DB_URL"),
+        Some(String::from("postgres://worker")),
+    );
+    assert_eq!(vars, expected);
+
+    DotenvLoader::new().add_prefix("NS_").load().unwrap();
+    assert_eq!(env::var("NS_APP_DB_URL").unwrap(), "postgres://app");
+    assert_eq!(env::var("NS_WORKER_DB_URL").unwrap(), "postgres://worker");
+
+    env::set_current_dir(dir.path().parent().unwrap()).unwrap();
+    dir.close().unwrap();
+}
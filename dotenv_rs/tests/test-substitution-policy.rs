@@ -0,0 +1,64 @@
+use dotenv_rs::*;
+use std::collections::HashMap;
+use std::fs;
+use tempfile::tempdir;
+
+/// A source that fails the test when read.
+struct Forbidden;
+
+impl EnvSource for Forbidden {
+    fn get(&self, key: &str) -> Option<String> {
+        panic!("read {} from the environment", key);
+    }
+}
+
+#[test]
+fn test_substitution_policy() {
+    let dir = tempdir().unwrap();
+    let env_path = dir.path().join(".env");
+    fs::write(
+        &env_path,
+        "POLICY_HOME=/srv\nPOLICY_DATA=${POLICY_HOME}/data\nPOLICY_USER=${POLICY_NAME:-nobody}\n",
+    )
+    .unwrap();
+    let env: HashMap<String, String> = vec![
+        ("POLICY_HOME".to_owned(), "/home/me".to_owned()),
+        ("POLICY_NAME".to_owned(), "me".to_owned()),
+    ]
+    .into_iter()
+    .collect();
+
+    let load = |policy| {
+        let mut vars = HashMap::new();
+        DotenvLoader::new()
+            .path(&env_path)
+            .source(env.clone())
+            .substitution_policy(policy)
+            .load_into(&mut vars)
+            .unwrap();
+        (vars["POLICY_DATA"].clone(), vars["POLICY_USER"].clone())
+    };
+    assert_eq!(
+        load(SubstitutionPolicy::EnvFirst),
+        ("/home/me/data".to_owned(), "me".to_owned())
+    );
+    assert_eq!(
+        load(SubstitutionPolicy::FileFirst),
+        ("/srv/data".to_owned(), "me".to_owned())
+    );
+    assert_eq!(
+        load(SubstitutionPolicy::EnvOnly),
+        ("/home/me/data".to_owned(), "me".to_owned())
+    );
+
+    let loader = DotenvLoader::new()
+        .path(&env_path)
+        .source(Forbidden)
+        .substitution_policy(SubstitutionPolicy::FileOnly);
+    let vars: HashMap<String, Option<String>> = loader.to_map().unwrap();
+    assert_eq!(vars["POLICY_DATA"].as_deref(), Some("/srv/data"));
+    assert_eq!(vars["POLICY_USER"].as_deref(), Some("nobody"));
+
+    let explanation = loader.explain("POLICY_DATA").unwrap();
+    assert_eq!(explanation.references[0].source, ValueSource::File);
+}
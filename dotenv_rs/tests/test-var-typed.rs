@@ -0,0 +1,52 @@
+mod common;
+
+use std::env;
+
+use dotenv_rs::*;
+
+use crate::common::*;
+
+#[test]
+fn test_var_typed() {
+    let dir = tempdir_with_dotenv(
+        "PORT=8080
+RATIO=0.25
+DEBUG=Yes
+VERBOSE=off
+HOSTS=\"a.example.com, b.example.com,,\"
+NOT_A_NUMBER=eighty
+",
+    )
+    .unwrap();
+
+    assert_eq!(var_parse::<u16, _>("PORT").unwrap(), 8080);
+    assert_eq!(var_parse::<f64, _>("RATIO").unwrap(), 0.25);
+    assert_eq!(var_or("PORT", 80u16).unwrap(), 8080);
+    assert_eq!(var_or("MISSING_PORT", 80u16).unwrap(), 80);
+    assert!(var_bool("DEBUG").unwrap());
+    assert!(!var_bool("VERBOSE").unwrap());
+    assert_eq!(
+        var_list("HOSTS", ",").unwrap(),
+        vec!["a.example.com", "b.example.com"]
+    );
+
+    match var_parse::<u16, _>("NOT_A_NUMBER") {
+        Err(Error::InvalidValue { key, message }) => {
+            assert_eq!(key, "NOT_A_NUMBER");
+            assert_eq!(message, "invalid digit found in string");
+        }
+        _ => panic!("Expected NOT_A_NUMBER not to be parsed"),
+    }
+    match var_bool("PORT") {
+        Err(Error::InvalidValue { key, .. }) => assert_eq!(key, "PORT"),
+        _ => panic!("Expected PORT not to be a boolean"),
+    }
+    assert!(var_or("NOT_A_NUMBER", 1u16).is_err());
+    assert!(matches!(
+        var_parse::<u16, _>("MISSING_PORT"),
+        Err(Error::EnvVar(env::VarError::NotPresent))
+    ));
+
+    env::set_current_dir(dir.path().parent().unwrap()).unwrap();
+    dir.close().unwrap();
+}
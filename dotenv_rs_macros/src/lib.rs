@@ -0,0 +1,108 @@
+//! Compile-time access to the variables of a .env file.
+//!
+//! The .env file is looked up from the directory of the crate using the macros
+//! (`CARGO_MANIFEST_DIR`) and its parents. As with `dotenv_rs::dotenv()`, variables already
+//! set in the environment of the compiler take precedence over the file.
+//!
+//! Cargo does not track the .env file: after editing it, touch a source file or run
+//! `cargo clean` so that the values are embedded again.
+
+extern crate proc_macro;
+
+use std::env;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{LitStr, Token};
+
+/// Expands to the value of a variable as a `&'static str`, failing the build when it is not
+/// defined. An optional second argument replaces the error message.
+///
+/// # Examples
+/// ```ignore
+/// use dotenv_rs_macros::dotenv;
+///
+/// const API_URL: &str = dotenv!("API_URL");
+/// const TOKEN: &str = dotenv!("TOKEN", "set TOKEN in .env before building");
+/// ```
+#[proc_macro]
+pub fn dotenv(input: TokenStream) -> TokenStream {
+    expand(input, |key, value, message| match value {
+        Some(value) => Ok(quote!(#value)),
+        None => Err(syn::Error::new(
+            key.span(),
+            message
+                .unwrap_or_else(|| format!("environment variable `{}` not defined", key.value())),
+        )),
+    })
+}
+
+/// Expands to `Some` value of a variable as a `&'static str`, or to `None` when it is not
+/// defined.
+///
+/// # Examples
+/// ```ignore
+/// use dotenv_rs_macros::dotenv_option;
+///
+/// const SENTRY_DSN: Option<&str> = dotenv_option!("SENTRY_DSN");
+/// ```
+#[proc_macro]
+pub fn dotenv_option(input: TokenStream) -> TokenStream {
+    expand(input, |_, value, _| match value {
+        Some(value) => Ok(quote!(::core::option::Option::Some(#value))),
+        None => Ok(quote!(::core::option::Option::None::<&'static str>)),
+    })
+}
+
+fn expand<F>(input: TokenStream, render: F) -> TokenStream
+where
+    F: FnOnce(&LitStr, Option<String>, Option<String>) -> syn::Result<proc_macro2::TokenStream>,
+{
+    let result = parse_args(input).and_then(|(key, message)| {
+        let value = lookup(&key)?;
+        render(&key, value, message)
+    });
+    match result {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Parses `"KEY"` or `"KEY", "message"`.
+fn parse_args(input: TokenStream) -> syn::Result<(LitStr, Option<String>)> {
+    let args = Punctuated::<LitStr, Token![,]>::parse_terminated.parse(input)?;
+    let mut args = args.into_iter();
+    let key = args
+        .next()
+        .ok_or_else(|| syn::Error::new(Span::call_site(), "expected a variable name"))?;
+    let message = args.next().map(|message| message.value());
+    if let Some(extra) = args.next() {
+        return Err(syn::Error::new(
+            extra.span(),
+            "expected at most two arguments",
+        ));
+    }
+    Ok((key, message))
+}
+
+fn lookup(key: &LitStr) -> syn::Result<Option<String>> {
+    if let Ok(value) = env::var(key.value()) {
+        return Ok(Some(value));
+    }
+
+    let mut loader = dotenv_rs::DotenvLoader::new();
+    if let Some(manifest_dir) = env::var_os("CARGO_MANIFEST_DIR") {
+        loader = loader.search_from(manifest_dir);
+    }
+    match loader.to_map() {
+        Ok(mut vars) => Ok(vars.remove(&key.value()).map(Option::unwrap_or_default)),
+        Err(ref err) if err.not_found() => Ok(None),
+        Err(err) => Err(syn::Error::new(
+            key.span(),
+            format!("failed to load .env: {}", err),
+        )),
+    }
+}
@@ -0,0 +1,24 @@
+use dotenv_rs_macros::{dotenv, dotenv_option};
+
+// the workspace's .env file is found from this crate's directory
+
+#[test]
+fn test_dotenv() {
+    assert_eq!(dotenv!("CODEGEN_TEST_VAR1"), "hello!");
+    assert_eq!(
+        dotenv!("CODEGEN_TEST_VAR2", "custom message"),
+        "'quotes within quotes'"
+    );
+}
+
+#[test]
+fn test_dotenv_in_const() {
+    const VALUE: &str = dotenv!("CODEGEN_TEST_VAR1");
+    assert_eq!(VALUE, "hello!");
+}
+
+#[test]
+fn test_dotenv_option() {
+    assert_eq!(dotenv_option!("CODEGEN_TEST_VAR1"), Some("hello!"));
+    assert_eq!(dotenv_option!("CODEGEN_TEST_MISSING_VAR"), None);
+}